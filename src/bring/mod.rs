@@ -1,111 +1,201 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
-use serde_json::Value;
+use reqwest::{RequestBuilder, Response, StatusCode};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
 
 mod types;
 pub use types::*;
 
+const BASE_URL: &str = "https://api.getbring.com/rest/v2";
+
+/// Tokens are refreshed this long before they expire, so a request never uses an expired one.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// After a failed login, no new login is tried for this long,
+/// so wrong credentials or an outage do not cause a login on every request.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// The connection to the Bring! API.
+/// The user is logged in with the first request and again whenever the session is lost.
+/// Clones share the session, so a refreshed token is seen by all of them.
 #[derive(Clone)]
 pub struct BringConnection {
     client: reqwest::Client,
+    email: String,
+    password: String,
+    login: Arc<RwLock<Login>>,
+}
+
+/// The login state shared by the clones of a connection.
+#[derive(Default)]
+struct Login {
+    session: Option<Session>,
+    /// When the last login failed and why.
+    failed: Option<(Instant, String)>,
+}
+
+impl Login {
+    fn valid_session(&self) -> Option<Session> {
+        self.session
+            .clone()
+            .filter(|session| !session.expires_soon())
+    }
+}
 
-    _name: String,
-    _email: String,
-    _password: String,
-    base_url: String,
+/// The tokens of a logged in user.
+#[derive(Clone)]
+struct Session {
     uuid: String,
-    headers: HeaderMap,
-    put_headers: HeaderMap,
-    _bearer_token: String,
-    _refresh_token: String,
+    bearer_token: String,
+    refresh_token: String,
+    expires_at: Instant,
+}
+
+impl Session {
+    fn from_response(response: AuthResponse, previous: Option<&Session>) -> Result<Self> {
+        let uuid = response
+            .uuid
+            .or_else(|| previous.map(|s| s.uuid.clone()))
+            .ok_or_else(|| anyhow!("Bring! did not return a user uuid"))?;
+        let refresh_token = response
+            .refresh_token
+            .or_else(|| previous.map(|s| s.refresh_token.clone()))
+            .unwrap_or_default();
+
+        Ok(Session {
+            uuid,
+            bearer_token: response.access_token,
+            refresh_token,
+            expires_at: Instant::now() + Duration::from_secs(response.expires_in),
+        })
+    }
+
+    fn expires_soon(&self) -> bool {
+        Instant::now() + EXPIRY_MARGIN >= self.expires_at
+    }
 }
 
 impl BringConnection {
-    pub async fn login(Config { email, password }: &Config) -> Result<Self> {
-        let base_url: &'static str = "https://api.getbring.com/rest/v2";
-        let login_params: HashMap<&'static str, String> = HashMap::from([
-            ("email", email.to_string()),
-            ("password", password.to_string()),
-        ]);
-
-        let client = reqwest::Client::new();
-        let resp = client
-            .post(base_url.to_string() + "/bringauth")
-            .form(&login_params)
+    /// Creates the connection without logging in, so the app also starts while Bring! is down.
+    pub fn new(Config { email, password }: &Config) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            email: email.to_string(),
+            password: password.to_string(),
+            login: Arc::new(RwLock::new(Login::default())),
+        }
+    }
+
+    /// Returns a session whose token is still valid.
+    /// An expiring token is refreshed, and if that fails or there is no session yet,
+    /// the user is logged in.
+    async fn session(&self) -> Result<Session> {
+        if let Some(session) = self.login.read().await.valid_session() {
+            return Ok(session);
+        }
+
+        let mut login = self.login.write().await;
+        // Another request might have renewed the session while waiting for the lock
+        if let Some(session) = login.valid_session() {
+            return Ok(session);
+        }
+
+        let refreshed = match &login.session {
+            Some(session) => refresh(&self.client, session).await.ok(),
+            None => None,
+        };
+        let session = match refreshed {
+            Some(session) => session,
+            None => self.log_in(&mut login).await?,
+        };
+
+        login.session = Some(session.clone());
+        Ok(session)
+    }
+
+    async fn log_in(&self, login: &mut Login) -> Result<Session> {
+        let recent_failure = login
+            .failed
+            .as_ref()
+            .filter(|(failed_at, _)| failed_at.elapsed() < RETRY_DELAY);
+        if let Some((_, reason)) = recent_failure {
+            return Err(anyhow!("Login to Bring! failed recently. {}", reason));
+        }
+
+        match authenticate(&self.client, &self.email, &self.password).await {
+            Ok(session) => {
+                login.failed = None;
+                Ok(session)
+            }
+            Err(err) => {
+                login.session = None;
+                login.failed = Some((Instant::now(), err.to_string()));
+                Err(err)
+            }
+        }
+    }
+
+    /// Marks the token as expired, so the next request refreshes it.
+    async fn invalidate(&self) {
+        if let Some(session) = self.login.write().await.session.as_mut() {
+            session.expires_at = Instant::now();
+        }
+    }
+
+    /// Sends the request with the headers of a valid session.
+    /// If Bring! rejects the token anyway, it is refreshed and the request is sent once more.
+    async fn send(
+        &self,
+        request: impl Fn(&reqwest::Client, &Session) -> RequestBuilder,
+    ) -> Result<Response> {
+        let session = self.session().await?;
+        let resp = request(&self.client, &session)
+            .headers(headers(&session)?)
             .send()
             .await?;
+        if resp.status() != StatusCode::UNAUTHORIZED {
+            return Ok(resp);
+        }
 
-        let data = resp.text().await.expect("Could not read response body");
-        let data: Value = serde_json::from_str(data.as_str()).expect("Could not parse JSON");
-
-        let name = data["name"].to_string().replace("\"", "");
-        let uuid = data["uuid"].to_string().replace("\"", "");
-        let bearer_token = data["access_token"].to_string().replace("\"", "");
-        let refresh_token = data["refresh_token"].to_string().replace("\"", "");
-
-        let mut headers: HeaderMap = HeaderMap::new();
-
-        headers.insert(
-            "X-BRING-API-KEY",
-            HeaderValue::from_static("cof4Nc6D8saplXjE3h3HXqHH8m7VU2i1Gs0g85Sp"),
-        );
-        headers.insert("X-BRING-CLIENT", HeaderValue::from_static("webApp"));
-        headers.insert("X-BRING-CLIENT-SOURCE", HeaderValue::from_static("webApp"));
-        headers.insert("X-BRING-COUNTRY", HeaderValue::from_static("DE"));
-        headers.insert(
-            "X-BRING-USER-UUID",
-            HeaderValue::from_static("2f7591c5-8a20-4a24-b0ac-5974cb557011"),
-        );
-        headers.insert(
-            AUTHORIZATION,
-            HeaderValue::from_str(format!("Bearer {bearer_token}").as_str()).unwrap(),
-        );
-
-        let mut put_headers: HeaderMap = headers.clone();
-        put_headers.insert(
-            CONTENT_TYPE,
-            HeaderValue::from_static("application/x-www-form-urlencoded; charset=UTF-8"),
-        );
-
-        return Ok(Self {
-            client,
-            _name: name,
-            _email: email.to_string(),
-            _password: password.to_string(),
-            base_url: base_url.to_string(),
-            uuid,
-            headers,
-            put_headers,
-            _bearer_token: bearer_token,
-            _refresh_token: refresh_token,
-        });
+        self.invalidate().await;
+        let session = self.session().await?;
+        Ok(request(&self.client, &session)
+            .headers(headers(&session)?)
+            .send()
+            .await?)
     }
 
     pub async fn get_all_lists(&self) -> Result<LoadListsResponse> {
         let resp = self
-            .client
-            .get(self.base_url.to_owned() + "/bringusers/" + self.uuid.as_str() + "/lists")
-            .headers(self.headers.clone())
-            .send()
+            .send(|client, session| {
+                client.get(format!("{BASE_URL}/bringusers/{}/lists", session.uuid))
+            })
             .await?;
 
-        let body = resp.text().await.unwrap();
-        let lists: LoadListsResponse = serde_json::from_str(body.as_str()).unwrap();
-        return Ok(lists);
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!("Could not load the lists. {}", body));
+        }
+
+        Ok(serde_json::from_str(&body)?)
     }
 
     pub async fn get_items_from_list(&self, list_uuid: String) -> Result<GetItemsResponse> {
         let resp = self
-            .client
-            .get(self.base_url.to_owned() + "/bringlists/" + list_uuid.as_str())
-            .headers(self.headers.clone())
-            .send()
+            .send(|client, _| client.get(format!("{BASE_URL}/bringlists/{list_uuid}")))
             .await?;
 
-        let body = resp.text().await.unwrap();
-        let items: GetItemsResponse = serde_json::from_str(body.as_str()).unwrap();
-        return Ok(items);
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!("Could not load the items. {}", body));
+        }
+
+        Ok(serde_json::from_str(&body)?)
     }
 
     pub async fn save_item(
@@ -121,21 +211,25 @@ impl BringConnection {
         params.insert("sender", String::from("null"));
 
         let resp = self
-            .client
-            .put(self.base_url.to_owned() + "/bringlists/" + list_uuid.as_str())
-            .headers(self.put_headers.clone())
-            .form(&params)
-            .send()
+            .send(|client, _| {
+                client
+                    .put(format!("{BASE_URL}/bringlists/{list_uuid}"))
+                    .header(
+                        CONTENT_TYPE,
+                        "application/x-www-form-urlencoded; charset=UTF-8",
+                    )
+                    .form(&params)
+            })
             .await?;
 
         let status = resp.status();
         if !status.is_success() {
-            let body = resp.text().await.unwrap();
+            let body = resp.text().await?;
             println!("{}", body);
             return Err(anyhow::anyhow!("Could not save item. {}", body));
         }
 
-        return Ok(());
+        Ok(())
     }
 
     pub async fn remove_item(&self, list_uuid: String, item_name: String) -> Result<()> {
@@ -147,21 +241,25 @@ impl BringConnection {
         params.insert("sender", String::from("null"));
 
         let resp = self
-            .client
-            .put(self.base_url.to_owned() + "/bringlists/" + list_uuid.as_str())
-            .headers(self.put_headers.clone())
-            .form(&params)
-            .send()
+            .send(|client, _| {
+                client
+                    .put(format!("{BASE_URL}/bringlists/{list_uuid}"))
+                    .header(
+                        CONTENT_TYPE,
+                        "application/x-www-form-urlencoded; charset=UTF-8",
+                    )
+                    .form(&params)
+            })
             .await?;
 
         let status = resp.status();
         if !status.is_success() {
-            let body = resp.text().await.unwrap();
+            let body = resp.text().await?;
             println!("{}", body);
             return Err(anyhow::anyhow!("Could not remove item. {}", body));
         }
 
-        return Ok(());
+        Ok(())
     }
 
     pub async fn move_to_recent_list(&self, list_uuid: String, item_name: String) -> Result<()> {
@@ -173,33 +271,98 @@ impl BringConnection {
         params.insert("sender", String::from("null"));
 
         let resp = self
-            .client
-            .put(self.base_url.to_owned() + "/bringlists/" + list_uuid.as_str())
-            .headers(self.put_headers.clone())
-            .form(&params)
-            .send()
+            .send(|client, _| {
+                client
+                    .put(format!("{BASE_URL}/bringlists/{list_uuid}"))
+                    .header(
+                        CONTENT_TYPE,
+                        "application/x-www-form-urlencoded; charset=UTF-8",
+                    )
+                    .form(&params)
+            })
             .await?;
 
         let status = resp.status();
         if !status.is_success() {
-            let body = resp.text().await.unwrap();
+            let body = resp.text().await?;
             return Err(anyhow::anyhow!("Could not move item to recently. {}", body));
         }
 
-        return Ok(());
+        Ok(())
     }
+}
 
-    pub async fn load_catalog(&self, locale: &str) -> Result<LoadCatalogResponse> {
-        let resp = self
-            .client
-            .get(format!(
-                "https://web.getbring.com/locale/catalog.{locale}.json"
-            ))
-            .send()
-            .await?;
+/// Loads the catalog of the locale, which is public and needs no login.
+pub async fn load_catalog(locale: &str) -> Result<LoadCatalogResponse> {
+    let resp = reqwest::get(format!(
+        "https://web.getbring.com/locale/catalog.{locale}.json"
+    ))
+    .await?;
+
+    let body = resp.text().await?;
+    let catalog: LoadCatalogResponse = serde_json::from_str(body.as_str())?;
+    Ok(catalog)
+}
+
+/// Logs the user in with the credentials.
+async fn authenticate(client: &reqwest::Client, email: &str, password: &str) -> Result<Session> {
+    let login_params = HashMap::from([("email", email), ("password", password)]);
+
+    let resp = client
+        .post(format!("{BASE_URL}/bringauth"))
+        .form(&login_params)
+        .send()
+        .await?;
+
+    Session::from_response(auth_response(resp).await?, None)
+}
+
+/// Gets a new token with the refresh token of the session.
+async fn refresh(client: &reqwest::Client, session: &Session) -> Result<Session> {
+    let params = HashMap::from([
+        ("grant_type", "refresh_token"),
+        ("refresh_token", session.refresh_token.as_str()),
+    ]);
 
-        let body = resp.text().await.unwrap();
-        let catalog: LoadCatalogResponse = serde_json::from_str(body.as_str()).unwrap();
-        return Ok(catalog);
+    let resp = client
+        .post(format!("{BASE_URL}/bringauth/token"))
+        .headers(headers(session)?)
+        .form(&params)
+        .send()
+        .await?;
+
+    Session::from_response(auth_response(resp).await?, Some(session))
+}
+
+async fn auth_response(resp: Response) -> Result<AuthResponse> {
+    let status = resp.status();
+    let body = resp.text().await?;
+    if !status.is_success() {
+        return Err(anyhow!("Could not authenticate at Bring!. {}", body));
     }
+
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// The headers every request to the API needs.
+fn headers(session: &Session) -> Result<HeaderMap> {
+    let mut headers: HeaderMap = HeaderMap::new();
+
+    headers.insert(
+        "X-BRING-API-KEY",
+        HeaderValue::from_static("cof4Nc6D8saplXjE3h3HXqHH8m7VU2i1Gs0g85Sp"),
+    );
+    headers.insert("X-BRING-CLIENT", HeaderValue::from_static("webApp"));
+    headers.insert("X-BRING-CLIENT-SOURCE", HeaderValue::from_static("webApp"));
+    headers.insert("X-BRING-COUNTRY", HeaderValue::from_static("DE"));
+    headers.insert(
+        "X-BRING-USER-UUID",
+        HeaderValue::from_static("2f7591c5-8a20-4a24-b0ac-5974cb557011"),
+    );
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", session.bearer_token))?,
+    );
+
+    Ok(headers)
 }
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Configuration for the Bring! API
 #[derive(Debug, Deserialize, Serialize)]
@@ -7,6 +8,22 @@ pub struct Config {
     pub password: String,
 }
 
+/// The response of a login or a token refresh.
+/// The refresh does not return the user, so these fields are optional.
+#[derive(Debug, Deserialize)]
+pub struct AuthResponse {
+    pub uuid: Option<String>,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    /// The lifetime of the access token in seconds.
+    #[serde(default = "default_expires_in")]
+    pub expires_in: u64,
+}
+
+fn default_expires_in() -> u64 {
+    3600
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct GetItemsResponseEntry {
     pub specification: String,
@@ -60,4 +77,51 @@ pub struct CatalogSectionList {
     pub sections: Vec<CatalogSectionsEntry>,
 }
 
-pub const LOCALE_DE: &'static str = "de-DE";
+/// The sections of the Bring! catalog and the items they contain.
+/// The items are looked up by their lowercase name.
+#[derive(Debug, Default)]
+pub struct Catalog {
+    /// The names of the sections in the order of the catalog.
+    pub sections: Vec<String>,
+    items: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Returns the section of the item with this name.
+    /// If there is no item with the exact name, the single words are tried from the last one,
+    /// so "Frische Sahne" ends up with "Sahne".
+    pub fn section_of(&self, name: &str) -> Option<&str> {
+        let name = name.trim().to_lowercase();
+        if let Some(section) = self.items.get(&name) {
+            return Some(section);
+        }
+
+        name.split_whitespace()
+            .rev()
+            .find_map(|word| self.items.get(word))
+            .map(String::as_str)
+    }
+}
+
+impl From<LoadCatalogResponse> for Catalog {
+    fn from(response: LoadCatalogResponse) -> Self {
+        let mut catalog = Catalog::default();
+
+        for section in response.catalog.sections {
+            for item in section.items {
+                catalog
+                    .items
+                    .insert(item.name.to_lowercase(), section.name.clone());
+                catalog
+                    .items
+                    .entry(item.item_id.to_lowercase())
+                    .or_insert_with(|| section.name.clone());
+            }
+            catalog.sections.push(section.name);
+        }
+
+        catalog
+    }
+}
+
+pub const LOCALE_DE: &str = "de-DE";
@@ -1,29 +1,212 @@
 use crate::food::Food;
+use crate::household::{self, Member};
+use anyhow::{Result, anyhow};
 use askama::Template;
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate, Weekday};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Template)]
-#[template(path = "calendar/day.html")]
+/// A slot for meals of a day like lunch or dinner.
+/// The slots are configured in the `ServerConfig` and are displayed in the configured order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MealSlot {
+    /// Identifies the slot in the database.
+    /// Must not contain any of the characters used to store the meals: `= | ; , @ #`
+    pub name: String,
+    pub label: String,
+    /// The number of portions that get planned when a food is added to this slot.
+    pub portions: i32,
+    /// The weekdays on which this slot is offered. If not set, it is offered on every day.
+    #[serde(default)]
+    pub weekdays: Option<Vec<Weekday>>,
+}
+
+impl MealSlot {
+    pub fn is_offered_on(&self, date: &NaiveDate) -> bool {
+        match &self.weekdays {
+            Some(weekdays) => weekdays.contains(&date.weekday()),
+            None => true,
+        }
+    }
+}
+
+/// The characters that separate the parts of the stored meals.
+const RESERVED_CHARACTERS: [char; 6] = ['=', '|', ';', ',', '@', '#'];
+
+/// Checks that meals can be stored under the slot name.
+/// The name must not be empty or contain any of the reserved characters, otherwise the stored
+/// meals could not be read anymore. Stored meals may belong to slots that are no longer
+/// configured, so this does not check the configuration.
+pub fn validate_slot_name(slot: &str) -> Result<()> {
+    if slot.is_empty() || slot.contains(RESERVED_CHARACTERS) {
+        return Err(anyhow!("Invalid slot name: {}", slot));
+    }
+
+    Ok(())
+}
+
+/// Checks that a newly chosen slot is configured and can be stored.
+pub fn validate_slot(slots: &[MealSlot], slot: &str) -> Result<()> {
+    validate_slot_name(slot)?;
+    if !slots.iter().any(|s| s.name == slot) {
+        return Err(anyhow!("Unknown slot: {}", slot));
+    }
+
+    Ok(())
+}
+
+/// The slots that are used if none are configured.
+pub fn default_slots() -> Vec<MealSlot> {
+    vec![
+        MealSlot {
+            name: "lunch".to_string(),
+            label: "Mittagessen".to_string(),
+            portions: 4,
+            weekdays: None,
+        },
+        MealSlot {
+            name: "dinner".to_string(),
+            label: "Abendessen".to_string(),
+            portions: 4,
+            weekdays: None,
+        },
+    ]
+}
+
+/// Returns the label of a slot. Slots that are no longer configured are labeled by their name.
+pub fn slot_label<'a>(slots: &'a [MealSlot], slot: &'a str) -> &'a str {
+    slots
+        .iter()
+        .find(|s| s.name == slot)
+        .map(|s| s.label.as_str())
+        .unwrap_or(slot)
+}
+
+/// A day holds one meal per slot.
 /// The foods get associated with a factor of how many times the recipe is needed.
 /// Example:
 ///     Recipe has 4 portions, but we need 6 portions. The factor is 1.5
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Day {
     pub id: Option<i32>,
     pub date: NaiveDate,
-    pub lunch: Vec<(Food, f32)>,
-    pub dinner: Vec<(Food, f32)>,
+    pub meals: Vec<Meal>,
+    /// A free text like "Oma kommt zu Besuch".
+    pub note: String,
 }
 
 impl Day {
-    pub fn new(date: NaiveDate) -> Self {
+    /// Creates an empty day with all slots that are offered on the given date.
+    pub fn new(date: NaiveDate, slots: &[MealSlot]) -> Self {
+        let meals = slots
+            .iter()
+            .filter(|s| s.is_offered_on(&date))
+            .map(|s| Meal {
+                slot: s.name.clone(),
+                foods: Vec::new(),
+                headcount: None,
+            })
+            .collect();
+
         Day {
             id: None,
             date,
-            lunch: Vec::new(),
-            dinner: Vec::new(),
+            meals,
+            note: String::new(),
         }
     }
+
+    /// Iterates over the planned foods of all meals.
+    pub fn planned_foods(&self) -> impl Iterator<Item = &PlannedFood> {
+        self.meals.iter().flat_map(|m| m.foods.iter())
+    }
+}
+
+/// All foods that are planned in a slot of a day.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Meal {
+    pub slot: String,
+    pub foods: Vec<PlannedFood>,
+    /// The number of people eating. If not set, the portions of the slot are used.
+    pub headcount: Option<i32>,
+}
+
+/// This displays a day in the calendar.
+#[derive(Debug, Template)]
+#[template(path = "calendar/day.html")]
+pub struct DayView<'a> {
+    pub day: &'a Day,
+    pub slots: &'a [MealSlot],
+    pub members: &'a [Member],
+}
+
+impl DayView<'_> {
+    fn label<'b>(&'b self, slot: &'b str) -> &'b str {
+        slot_label(self.slots, slot)
+    }
+
+    /// The portions a food gets planned with when it is added to the given slot.
+    fn default_portions(&self, slot: &str) -> i32 {
+        self.slots
+            .iter()
+            .find(|s| s.name == slot)
+            .map(|s| s.portions)
+            .unwrap_or(4)
+    }
+
+    /// The warnings for all household members who eat the slot but must not eat the food.
+    fn warnings(&self, slot: &str, food: &Food) -> Vec<String> {
+        household::warnings(self.members, slot, food)
+    }
+
+    /// The number of people eating the given meal.
+    fn portions(&self, meal: &Meal) -> i32 {
+        meal.headcount
+            .unwrap_or_else(|| self.default_portions(&meal.slot))
+    }
+}
+
+/// A food that is planned for a meal of a day.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlannedFood {
+    pub food: Food,
+    pub factor: f32,
+    /// If set, this food is not cooked again but the leftovers of the referenced meal are eaten.
+    /// Its ingredients are already covered by the referenced meal.
+    pub leftovers_of: Option<MealRef>,
+    /// The id of the recurring rule which generated this food. Generated foods are not stored.
+    #[serde(default)]
+    pub from_rule: Option<i32>,
+}
+
+impl PlannedFood {
+    pub fn is_leftovers(&self) -> bool {
+        self.leftovers_of.is_some()
+    }
+}
+
+/// References a meal by its date and slot.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MealRef {
+    pub date: NaiveDate,
+    pub slot: String,
+}
+
+/// Returns the german name of the weekday of the given date.
+pub fn weekday_name(date: &NaiveDate) -> &'static str {
+    weekday_label(date.weekday())
+}
+
+/// Returns the german name of the weekday.
+pub fn weekday_label(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Montag",
+        Weekday::Tue => "Dienstag",
+        Weekday::Wed => "Mittwoch",
+        Weekday::Thu => "Donnerstag",
+        Weekday::Fri => "Freitag",
+        Weekday::Sat => "Samstag",
+        Weekday::Sun => "Sonntag",
+    }
 }
 
 /// This is used in the template to calculate the amount of ingredients needed.
@@ -35,6 +218,74 @@ pub fn mult_portions(portions: &i32, factor: &f32) -> f32 {
 pub struct ResponseDay {
     pub id: Option<i32>,
     pub date: NaiveDate,
-    pub lunch: Vec<(i32, f32)>,
-    pub dinner: Vec<(i32, f32)>,
+    pub meals: Vec<ResponseMeal>,
+    /// The note of the day. If not set, the stored note is kept.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// A meal like it is submitted by the frontend and stored in the database.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseMeal {
+    pub slot: String,
+    pub foods: Vec<ResponseFood>,
+    #[serde(default)]
+    pub headcount: Option<i32>,
+}
+
+impl From<&Meal> for ResponseMeal {
+    fn from(meal: &Meal) -> Self {
+        ResponseMeal {
+            slot: meal.slot.clone(),
+            foods: meal
+                .foods
+                .iter()
+                .filter_map(|planned| {
+                    Some(ResponseFood {
+                        id: planned.food.id?,
+                        factor: planned.factor,
+                        leftovers_of: planned.leftovers_of.clone(),
+                    })
+                })
+                .collect(),
+            headcount: meal.headcount,
+        }
+    }
+}
+
+/// A planned food like it is submitted by the frontend and stored in the database.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseFood {
+    pub id: i32,
+    pub factor: f32,
+    #[serde(default)]
+    pub leftovers_of: Option<MealRef>,
+}
+
+/// Requests to copy the week that contains `from` to the week that contains `to`.
+#[derive(Debug, Deserialize)]
+pub struct CopyWeekRequest {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+/// Requests to move or swap the meals of two days.
+#[derive(Debug, Deserialize)]
+pub struct DayPairRequest {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+/// Requests to move the foods of a meal to another meal.
+#[derive(Debug, Deserialize)]
+pub struct MoveMealRequest {
+    pub from: MealRef,
+    pub to: MealRef,
+}
+
+/// Requests to clear all meals from `from` until `to` (inclusive).
+#[derive(Debug, Deserialize)]
+pub struct ClearRangeRequest {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
 }
@@ -1,15 +1,28 @@
 use super::food::get_foods_in;
-use crate::calendar::{Day, ResponseDay};
+use super::recurring::query_rules;
+use crate::calendar::{
+    Day, Meal, MealRef, MealSlot, PlannedFood, ResponseDay, ResponseFood, ResponseMeal,
+    validate_slot, validate_slot_name,
+};
 use crate::db::Connection;
-use crate::food::Food;
-use anyhow::Result;
+use crate::food::{Food, FoodStats};
+use crate::recurring::RecurringRule;
+use crate::utils::string_to_vec;
+use anyhow::{Context, Result, anyhow};
 use chrono::NaiveDate;
+use std::collections::HashMap;
+use tokio_postgres::Transaction;
 
 /// Fetches a range of days from the database.
+/// Every day contains a meal for each slot that is offered on that day.
+///
+/// Starting today, the meals generated by recurring rules are added to all slots that were not
+/// saved yet.
 pub async fn get_days(
     mut conn: Connection<'_>,
     from: NaiveDate,
     to: NaiveDate,
+    slots: &[MealSlot],
 ) -> Result<Vec<Day>> {
     let tx = conn.transaction().await?;
 
@@ -22,23 +35,44 @@ pub async fn get_days(
 
     // fetch all referenced foods
     let mut ids = Vec::new();
+    let mut stored_slots: HashMap<NaiveDate, Vec<String>> = HashMap::new();
     for row in &day_rows {
-        let lunch = db_string_to_foods(row.get("lunch"));
-        let dinner = db_string_to_foods(row.get("dinner"));
-        lunch.iter().for_each(|(id, _)| ids.push(*id));
-        dinner.iter().for_each(|(id, _)| ids.push(*id));
+        for meal in db_string_to_meals(row.get("meals"))? {
+            meal.foods.iter().for_each(|f| ids.push(f.id));
+            stored_slots
+                .entry(row.get("date"))
+                .or_default()
+                .push(meal.slot);
+        }
+    }
+
+    // Rules only apply from today on
+    let today = chrono::Local::now().date_naive();
+    let rules = if to > today {
+        query_rules(&tx).await?
+    } else {
+        Vec::new()
+    };
+    for rule in &rules {
+        match (rule.food_id, &rule.tag) {
+            (Some(id), _) => ids.push(id),
+            (None, Some(tag)) => ids.extend(get_food_ids_with_tag(&tx, tag).await?),
+            (None, None) => {}
+        }
     }
+
     let foods = get_foods_in(&tx, &ids).await?;
 
     tx.commit().await?;
 
     let mut current_date = from;
     let mut days = Vec::new();
-    let mut day_iter = day_rows
+    let stored_days = day_rows
         .iter()
         .rev()
-        .map(|row| row_to_day(row, &foods))
-        .peekable();
+        .map(|row| row_to_day(row, &foods, slots))
+        .collect::<Result<Vec<_>>>()?;
+    let mut day_iter = stored_days.into_iter().peekable();
 
     // Iterate over all days in the range. Fill in missing days if needed.
     while current_date < to {
@@ -48,26 +82,166 @@ pub async fn get_days(
                 days.push(day_iter.next().unwrap());
             }
             _ => {
-                days.push(Day::new(current_date));
+                days.push(Day::new(current_date, slots));
             }
         }
 
         // Increment to next day
-        current_date = current_date.succ_opt().expect(&format!(
-            "Exceeded maximum date by incrementing {:?}",
-            current_date
-        ));
+        current_date = current_date
+            .succ_opt()
+            .unwrap_or_else(|| panic!("Exceeded maximum date by incrementing {:?}", current_date));
     }
 
+    apply_rules(&mut days, &rules, &foods, &stored_slots, today);
+
     Ok(days)
 }
 
+/// Adds the foods of the rules to the slots of the days from `today` on which were not stored.
+fn apply_rules(
+    days: &mut [Day],
+    rules: &[RecurringRule],
+    foods: &[Food],
+    stored_slots: &HashMap<NaiveDate, Vec<String>>,
+    today: NaiveDate,
+) {
+    for day in days.iter_mut().filter(|d| d.date >= today) {
+        let stored = stored_slots.get(&day.date);
+
+        for rule in rules.iter().filter(|r| r.applies_on(day.date)) {
+            if stored.is_some_and(|slots| slots.contains(&rule.slot)) {
+                continue;
+            }
+            let Some(food) = rule.pick_food(day.date, foods) else {
+                continue;
+            };
+
+            let planned = PlannedFood {
+                food: food.clone(),
+                factor: rule.factor,
+                leftovers_of: None,
+                from_rule: rule.id,
+            };
+            match day.meals.iter_mut().find(|m| m.slot == rule.slot) {
+                Some(meal) => meal.foods.push(planned),
+                None => day.meals.push(Meal {
+                    slot: rule.slot.clone(),
+                    foods: vec![planned],
+                    headcount: None,
+                }),
+            }
+        }
+    }
+}
+
+/// Fetches the stored day with the given id. Returns `None` if there is no such day.
+pub async fn get_day(conn: Connection<'_>, id: i32, slots: &[MealSlot]) -> Result<Option<Day>> {
+    let row = conn
+        .query_opt("SELECT date FROM day WHERE id = $1", &[&id])
+        .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let date: NaiveDate = row.get("date");
+    let days = get_days(conn, date, date.succ_opt().unwrap(), slots).await?;
+
+    Ok(days.into_iter().next())
+}
+
+/// Fetches the days with the meals that the leftovers of the given days belong to.
+/// Days without leftovers of them are not included.
+pub async fn get_leftover_sources(
+    conn: Connection<'_>,
+    days: &[Day],
+    slots: &[MealSlot],
+) -> Result<Vec<Day>> {
+    let dates: Vec<NaiveDate> = days
+        .iter()
+        .flat_map(|day| day.planned_foods())
+        .filter_map(|planned| planned.leftovers_of.as_ref())
+        .map(|meal| meal.date)
+        .collect();
+    let (Some(from), Some(to)) = (dates.iter().min(), dates.iter().max()) else {
+        return Ok(Vec::new());
+    };
+
+    let days = get_days(conn, *from, to.succ_opt().unwrap(), slots).await?;
+
+    Ok(days
+        .into_iter()
+        .filter(|day| dates.contains(&day.date))
+        .collect())
+}
+
+/// Fetches the ids of all foods with the given tag.
+async fn get_food_ids_with_tag(tx: &Transaction<'_>, tag: &str) -> Result<Vec<i32>> {
+    let rows = tx.query("SELECT id, tags FROM food", &[]).await?;
+
+    let ids = rows
+        .iter()
+        .filter(|row| {
+            string_to_vec(row.get("tags"))
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(tag))
+        })
+        .map(|row| row.get("id"))
+        .collect();
+
+    Ok(ids)
+}
+
+/// Calculates the statistics of all foods that were ever planned.
+///
+/// Days up to and including `today` count as cooked. `times_cooked` only counts the days starting
+/// at `since`.
+pub async fn get_food_stats(
+    mut conn: Connection<'_>,
+    today: NaiveDate,
+    since: NaiveDate,
+) -> Result<HashMap<i32, FoodStats>> {
+    let tx = conn.transaction().await?;
+    let day_rows = tx
+        .query("SELECT date, meals FROM day ORDER BY date ASC", &[])
+        .await?;
+    tx.commit().await?;
+
+    let mut stats: HashMap<i32, FoodStats> = HashMap::new();
+
+    for row in &day_rows {
+        let date: NaiveDate = row.get("date");
+        let meals = db_string_to_meals(row.get("meals"))?;
+
+        // Eating leftovers does not count as cooking the food again
+        for planned in meals.iter().flat_map(|m| m.foods.iter()) {
+            if planned.leftovers_of.is_some() {
+                continue;
+            }
+
+            let food_stats = stats.entry(planned.id).or_default();
+
+            if date <= today {
+                // The rows are ordered ascending, so the latest date is the last one
+                food_stats.last_cooked = Some(date);
+                if date >= since {
+                    food_stats.times_cooked += 1;
+                }
+            } else if food_stats.next_planned.is_none() {
+                food_stats.next_planned = Some(date);
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
 /// Updates or creates a non existing day in the database.
+/// All meals of the day are replaced with the given meals.
+/// The note is only replaced if the given day has one.
 ///
 /// WARNING: Carefull this allows to create duplicates of a day if two requests interfere.
 pub async fn update_day(mut conn: Connection<'_>, mut day: ResponseDay) -> Result<i32> {
-    let lunch = foods_to_db_string(day.lunch.into_iter());
-    let dinner = foods_to_db_string(day.dinner.into_iter());
+    let meals = meals_to_db_string(day.meals.into_iter())?;
 
     let tx = conn.transaction().await?;
 
@@ -85,8 +259,8 @@ pub async fn update_day(mut conn: Connection<'_>, mut day: ResponseDay) -> Resul
     let day_id = match day.id {
         Some(id) => {
             tx.execute(
-                "UPDATE day SET lunch = $2, dinner = $3 WHERE id = $1",
-                &[&id, &lunch, &dinner],
+                "UPDATE day SET meals = $2, note = COALESCE($3, note) WHERE id = $1",
+                &[&id, &meals, &day.note],
             )
             .await?;
 
@@ -94,8 +268,8 @@ pub async fn update_day(mut conn: Connection<'_>, mut day: ResponseDay) -> Resul
         }
         None => tx
             .query_one(
-                "INSERT INTO day (date, lunch, dinner) VALUES ($1, $2, $3) RETURNING id",
-                &[&day.date, &lunch, &dinner],
+                "INSERT INTO day (date, meals, note) VALUES ($1, $2, COALESCE($3, '')) RETURNING id",
+                &[&day.date, &meals, &day.note],
             )
             .await?
             .get("id"),
@@ -106,67 +280,640 @@ pub async fn update_day(mut conn: Connection<'_>, mut day: ResponseDay) -> Resul
     Ok(day_id)
 }
 
-fn row_to_day(row: &tokio_postgres::Row, foods: &Vec<Food>) -> Day {
-    let id: i32 = row.get("id");
-    let date: NaiveDate = row.get("date");
-    let lunch = db_string_to_foods(row.get("lunch"));
-    let dinner = db_string_to_foods(row.get("dinner"));
+/// Removes the day with the given date including its note.
+/// Recurring rules apply to the day again afterwards.
+pub async fn delete_day(mut conn: Connection<'_>, date: NaiveDate) -> Result<()> {
+    let tx = conn.transaction().await?;
 
-    let lunch = lunch
-        .iter()
-        .filter_map(|(id, factor)| {
-            let food = foods.iter().find(|f| f.id.unwrap() == *id);
-            match food {
-                Some(f) => Some((f.clone(), *factor)),
-                None => None,
-            }
+    tx.execute("DELETE FROM day WHERE date = $1", &[&date])
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Copies all meals of the week starting at `from` to the week starting at `to`.
+/// The meals of the target week are replaced. Leftovers are moved along with their meals.
+pub async fn copy_week(mut conn: Connection<'_>, from: NaiveDate, to: NaiveDate) -> Result<()> {
+    let offset = to - from;
+    let tx = conn.transaction().await?;
+
+    for date in from.iter_days().take(7) {
+        let meals = get_meals(&tx, date)
+            .await?
+            .into_iter()
+            .map(|meal| shift_leftovers(meal, |d| d + offset))
+            .collect();
+        set_meals(&tx, date + offset, meals).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Swaps all meals of the two days. Leftovers of the meals are swapped along with them.
+pub async fn swap_days(mut conn: Connection<'_>, a: NaiveDate, b: NaiveDate) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    let meals_a = get_meals(&tx, a).await?;
+    let meals_b = get_meals(&tx, b).await?;
+    set_meals(&tx, a, meals_b).await?;
+    set_meals(&tx, b, meals_a).await?;
+
+    repoint_leftovers(&tx, &[a, b], |meal| match meal.date {
+        d if d == a => Some(MealRef {
+            date: b,
+            slot: meal.slot.clone(),
+        }),
+        d if d == b => Some(MealRef {
+            date: a,
+            slot: meal.slot.clone(),
+        }),
+        _ => None,
+    })
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Moves all meals of a day to another day. The meals of the target day are replaced and the
+/// source day is left empty, so recurring rules do not fill it again.
+/// Leftovers of the meals are moved along with them. Moving a day onto itself changes nothing.
+pub async fn move_day(
+    mut conn: Connection<'_>,
+    from: NaiveDate,
+    to: NaiveDate,
+    slots: &[MealSlot],
+) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    let meals = get_meals(&tx, from).await?;
+    for (date, meals) in moved_day(from, to, meals, slots) {
+        set_meals(&tx, date, meals).await?;
+    }
+
+    repoint_leftovers(&tx, &[from], |meal| {
+        (meal.date == from).then(|| MealRef {
+            date: to,
+            slot: meal.slot.clone(),
         })
-        .collect();
+    })
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Moves the foods of a single meal to another meal. They are added after the foods that are
+/// already planned in the target meal. Leftovers of the meal are moved along with it.
+pub async fn move_meal(
+    mut conn: Connection<'_>,
+    from: &MealRef,
+    to: &MealRef,
+    slots: &[MealSlot],
+) -> Result<()> {
+    validate_slot(slots, &to.slot)?;
+
+    let tx = conn.transaction().await?;
+
+    let mut from_meals = get_meals(&tx, from.date).await?;
+    let foods = match from_meals.iter_mut().find(|m| m.slot == from.slot) {
+        Some(meal) => std::mem::take(&mut meal.foods),
+        None => Vec::new(),
+    };
+    set_meals(&tx, from.date, from_meals).await?;
+
+    let mut to_meals = get_meals(&tx, to.date).await?;
+    match to_meals.iter_mut().find(|m| m.slot == to.slot) {
+        Some(meal) => meal.foods.extend(foods),
+        None => to_meals.push(ResponseMeal {
+            slot: to.slot.clone(),
+            foods,
+            headcount: None,
+        }),
+    }
+    set_meals(&tx, to.date, to_meals).await?;
+
+    repoint_leftovers(&tx, &[from.date], |meal| (meal == from).then(|| to.clone())).await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Removes all meals of the days in `from..to`.
+/// The emptied slots are stored, so recurring rules do not fill the days again.
+pub async fn clear_range(
+    mut conn: Connection<'_>,
+    from: NaiveDate,
+    to: NaiveDate,
+    slots: &[MealSlot],
+) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    for date in from.iter_days().take_while(|date| *date < to) {
+        set_meals(&tx, date, empty_meals(slots)).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
 
-    let dinner = dinner
+/// Fetches the stored meals of a single day. Returns no meals if the day does not exist.
+async fn get_meals(tx: &Transaction<'_>, date: NaiveDate) -> Result<Vec<ResponseMeal>> {
+    let row = tx
+        .query_opt("SELECT meals FROM day WHERE date = $1", &[&date])
+        .await?;
+
+    match row {
+        Some(row) => db_string_to_meals(row.get("meals")),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Replaces the meals of a single day. The day is created if it does not exist.
+async fn set_meals(tx: &Transaction<'_>, date: NaiveDate, meals: Vec<ResponseMeal>) -> Result<()> {
+    let meals = meals_to_db_string(meals.into_iter())?;
+
+    let updated = tx
+        .execute(
+            "UPDATE day SET meals = $2 WHERE date = $1",
+            &[&date, &meals],
+        )
+        .await?;
+    if updated == 0 {
+        tx.execute(
+            "INSERT INTO day (date, meals) VALUES ($1, $2)",
+            &[&date, &meals],
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Returns the days and their meals to store when the meals of `from` are moved to `to`.
+fn moved_day(
+    from: NaiveDate,
+    to: NaiveDate,
+    meals: Vec<ResponseMeal>,
+    slots: &[MealSlot],
+) -> Vec<(NaiveDate, Vec<ResponseMeal>)> {
+    if from == to {
+        return Vec::new();
+    }
+
+    vec![(to, meals), (from, empty_meals(slots))]
+}
+
+/// Updates the leftovers on all days that belong to a meal on one of the `dates`.
+/// `map` returns the new place of a meal or `None` if it stays where it is.
+/// Has to be called after the meals were moved, so the leftovers that were moved with them
+/// are updated as well.
+async fn repoint_leftovers(
+    tx: &Transaction<'_>,
+    dates: &[NaiveDate],
+    map: impl Fn(&MealRef) -> Option<MealRef>,
+) -> Result<()> {
+    let mut days: Vec<NaiveDate> = Vec::new();
+    for date in dates {
+        let rows = tx
+            .query(
+                "SELECT date FROM day WHERE meals LIKE $1",
+                &[&format!("%,{}@%", date)],
+            )
+            .await?;
+        days.extend(rows.iter().map(|row| row.get::<_, NaiveDate>("date")));
+    }
+    days.sort();
+    days.dedup();
+
+    for date in days {
+        let mut meals = get_meals(tx, date).await?;
+        if repoint_meals(&mut meals, &map) {
+            set_meals(tx, date, meals).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the meals the leftovers belong to by `map`. Returns whether any leftovers changed.
+fn repoint_meals(meals: &mut [ResponseMeal], map: impl Fn(&MealRef) -> Option<MealRef>) -> bool {
+    let mut changed = false;
+    for food in meals.iter_mut().flat_map(|m| m.foods.iter_mut()) {
+        if let Some(meal) = food.leftovers_of.as_ref().and_then(&map) {
+            food.leftovers_of = Some(meal);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Returns an empty meal for every configured slot.
+/// Stored empty slots keep recurring rules from planning foods in them.
+fn empty_meals(slots: &[MealSlot]) -> Vec<ResponseMeal> {
+    slots
         .iter()
-        .filter_map(|(id, factor)| {
-            let food = foods.iter().find(|f| f.id.unwrap() == *id);
-            match food {
-                Some(f) => Some((f.clone(), *factor)),
-                None => None,
-            }
+        .map(|slot| ResponseMeal {
+            slot: slot.name.clone(),
+            foods: Vec::new(),
+            headcount: None,
         })
-        .collect();
+        .collect()
+}
+
+/// Maps the dates the leftovers in the given meal refer to.
+/// This keeps the references intact when meals are copied to other days.
+pub(crate) fn shift_leftovers(
+    mut meal: ResponseMeal,
+    map: impl Fn(NaiveDate) -> NaiveDate,
+) -> ResponseMeal {
+    for food in meal.foods.iter_mut() {
+        if let Some(source) = food.leftovers_of.as_mut() {
+            source.date = map(source.date);
+        }
+    }
+    meal
+}
+
+/// Converts a row of the day table to a `Day`.
+/// The meals are ordered like the configured slots. Slots that are not offered on this day are
+/// only included if they contain foods. Meals of slots that are no longer configured come last.
+fn row_to_day(row: &tokio_postgres::Row, foods: &[Food], slots: &[MealSlot]) -> Result<Day> {
+    let id: i32 = row.get("id");
+    let date: NaiveDate = row.get("date");
+    let mut stored_meals = db_string_to_meals(row.get("meals"))
+        .with_context(|| format!("Could not read the meals of {}", date))?;
 
-    Day {
+    let to_planned_food = |planned: ResponseFood| {
+        let food = foods.iter().find(|f| f.id.unwrap() == planned.id);
+        food.map(|f| PlannedFood {
+            food: f.clone(),
+            factor: planned.factor,
+            leftovers_of: planned.leftovers_of,
+            from_rule: None,
+        })
+    };
+    let to_meal = |meal: ResponseMeal| Meal {
+        slot: meal.slot,
+        foods: meal.foods.into_iter().filter_map(to_planned_food).collect(),
+        headcount: meal.headcount,
+    };
+
+    let mut meals = Vec::new();
+    for slot in slots {
+        let stored = stored_meals
+            .iter()
+            .position(|m| m.slot == slot.name)
+            .map(|i| stored_meals.remove(i));
+
+        match stored {
+            Some(meal) => meals.push(to_meal(meal)),
+            None if slot.is_offered_on(&date) => meals.push(Meal {
+                slot: slot.name.clone(),
+                foods: Vec::new(),
+                headcount: None,
+            }),
+            None => {}
+        }
+    }
+    meals.extend(
+        stored_meals
+            .into_iter()
+            .filter(|m| !m.foods.is_empty())
+            .map(to_meal),
+    );
+
+    Ok(Day {
         id: Some(id),
         date,
-        lunch,
-        dinner,
+        meals,
+        note: row.get("note"),
+    })
+}
+
+/// Convert meals to a string that can be stored in the database.
+/// A headcount is appended to the slot name.
+/// Example: [lunch: [(1, 1.5)], dinner for 6: [(2, 2.0)]] -> "lunch=1,1.5|dinner#6=2,2.0"
+///
+/// Fails if a meal or the meal that leftovers belong to has a slot name that cannot be stored.
+/// Slots that are no longer configured are kept, like `row_to_day` does.
+pub(super) fn meals_to_db_string(meals: impl Iterator<Item = ResponseMeal>) -> Result<String> {
+    let mut strings = Vec::new();
+    for meal in meals {
+        validate_slot_name(&meal.slot)?;
+        let slot = match meal.headcount {
+            Some(headcount) => format!("{}#{}", meal.slot, headcount),
+            None => meal.slot,
+        };
+        strings.push(format!(
+            "{}={}",
+            slot,
+            foods_to_db_string(meal.foods.into_iter())?
+        ));
+    }
+
+    Ok(strings.join("|"))
+}
+
+/// Convert a string from the database to a vector of meals.
+/// Example: "lunch=1,1.5|dinner#6=2,2.0" -> [lunch: [(1, 1.5)], dinner for 6: [(2, 2.0)]]
+pub(super) fn db_string_to_meals(s: &str) -> Result<Vec<ResponseMeal>> {
+    if s.is_empty() {
+        return Ok(Vec::new());
     }
+
+    s.split('|')
+        .map(|s| {
+            let (slot, foods) = s
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid meal: {}", s))?;
+            let (slot, headcount) = match slot.split_once('#') {
+                Some((slot, headcount)) => (slot, Some(headcount.parse()?)),
+                None => (slot, None),
+            };
+            Ok(ResponseMeal {
+                slot: slot.to_string(),
+                foods: db_string_to_foods(foods)?,
+                headcount,
+            })
+        })
+        .collect()
 }
 
-/// Convert food ids and factors to a string that can be stored in the database.
-/// Example: [(1, 1.5), (2, 2.0)] -> "1,1.5;2,2.0"
-fn foods_to_db_string(pairs: impl Iterator<Item = (i32, f32)>) -> String {
+/// Convert planned foods to a string that can be stored in the database.
+/// Leftovers additionally store the date and slot of the meal they belong to.
+/// Example: [(1, 1.5), (2, 2.0, leftovers of dinner on 2025-03-01)] -> "1,1.5;2,2.0,2025-03-01@dinner"
+fn foods_to_db_string(foods: impl Iterator<Item = ResponseFood>) -> Result<String> {
     let mut s = String::new();
-    for (id, factor) in pairs {
-        s.push_str(&id.to_string());
+    for food in foods {
+        s.push_str(&food.id.to_string());
         s.push(',');
-        s.push_str(&factor.to_string());
+        s.push_str(&food.factor.to_string());
+        if let Some(MealRef { date, slot }) = food.leftovers_of {
+            validate_slot_name(&slot)?;
+            s.push_str(&format!(",{}@{}", date, slot));
+        }
         s.push(';');
     }
     s.pop();
-    s
+    Ok(s)
 }
 
-/// Convert a string from the database to a vector of food ids and factors.
-/// Example: "1,1.5;2,2.0" -> [(1, 1.5), (2, 2.0)]
-fn db_string_to_foods(s: &str) -> Vec<(i32, f32)> {
+/// Convert a string from the database to a vector of planned foods.
+/// Example: "1,1.5;2,2.0,2025-03-01@dinner" -> [(1, 1.5), (2, 2.0, leftovers of dinner on 2025-03-01)]
+fn db_string_to_foods(s: &str) -> Result<Vec<ResponseFood>> {
     if s.is_empty() {
-        return Vec::new();
+        return Ok(Vec::new());
     }
 
     s.split(';')
         .map(|s| {
-            let (id, factor) = s.split_once(',').unwrap();
-            (id.parse().unwrap(), factor.parse().unwrap())
+            let mut parts = s.split(',');
+            let invalid = || anyhow!("Invalid planned food: {}", s);
+            let id = parts.next().ok_or_else(invalid)?.parse()?;
+            let factor = parts.next().ok_or_else(invalid)?.parse()?;
+            let leftovers_of = match parts.next() {
+                Some(meal) => {
+                    let (date, slot) = meal.split_once('@').ok_or_else(invalid)?;
+                    Some(MealRef {
+                        date: date.parse()?,
+                        slot: slot.to_string(),
+                    })
+                }
+                None => None,
+            };
+
+            Ok(ResponseFood {
+                id,
+                factor,
+                leftovers_of,
+            })
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::default_slots;
+
+    fn leftovers(slot: &str) -> ResponseMeal {
+        ResponseMeal {
+            slot: "lunch".to_string(),
+            foods: vec![ResponseFood {
+                id: 2,
+                factor: 1.0,
+                leftovers_of: Some(MealRef {
+                    date: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+                    slot: slot.to_string(),
+                }),
+            }],
+            headcount: None,
+        }
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    fn pizza() -> Food {
+        Food {
+            id: Some(7),
+            name: "Pizza".to_string(),
+            tags: Vec::new(),
+            details: String::new(),
+            portions: 4,
+            ingredients: Vec::new(),
+        }
+    }
+
+    fn every_day(slot: &str) -> RecurringRule {
+        RecurringRule {
+            id: Some(1),
+            weekday: None,
+            interval_days: Some(1),
+            slot: slot.to_string(),
+            food_id: Some(7),
+            tag: None,
+            factor: 1.0,
+            start: date(1),
+            end: None,
+        }
+    }
+
+    fn rule_foods(day: &Day, slot: &str) -> usize {
+        day.meals
+            .iter()
+            .filter(|m| m.slot == slot)
+            .flat_map(|m| m.foods.iter())
+            .filter(|f| f.from_rule.is_some())
+            .count()
+    }
+
+    #[test]
+    fn rules_fill_future_slots_only() {
+        let slots = default_slots();
+        let mut days: Vec<Day> = (3..6).map(|d| Day::new(date(d), &slots)).collect();
+
+        apply_rules(
+            &mut days,
+            &[every_day("dinner")],
+            &[pizza()],
+            &HashMap::new(),
+            date(4),
+        );
+
+        assert_eq!(rule_foods(&days[0], "dinner"), 0);
+        assert_eq!(rule_foods(&days[1], "dinner"), 1);
+        assert_eq!(rule_foods(&days[2], "dinner"), 1);
+        assert_eq!(rule_foods(&days[1], "lunch"), 0);
+    }
+
+    #[test]
+    fn rules_do_not_fill_stored_slots() {
+        let slots = default_slots();
+        let mut days = vec![Day::new(date(4), &slots)];
+        let stored = HashMap::from([(date(4), vec!["lunch".to_string()])]);
+
+        apply_rules(
+            &mut days,
+            &[every_day("lunch"), every_day("dinner")],
+            &[pizza()],
+            &stored,
+            date(4),
+        );
+
+        assert_eq!(rule_foods(&days[0], "lunch"), 0);
+        assert_eq!(rule_foods(&days[0], "dinner"), 1);
+    }
+
+    #[test]
+    fn rules_without_food_plan_nothing() {
+        let slots = default_slots();
+        let mut days = vec![Day::new(date(4), &slots)];
+
+        apply_rules(
+            &mut days,
+            &[every_day("dinner")],
+            &[],
+            &HashMap::new(),
+            date(4),
+        );
+
+        assert_eq!(rule_foods(&days[0], "dinner"), 0);
+    }
+
+    #[test]
+    fn meals_round_trip() {
+        let stored = "lunch=1,1.5;2,2,2025-03-01@dinner|dinner#6=3,0.5|lunch=";
+        let meals = db_string_to_meals(stored).unwrap();
+
+        assert_eq!(meals.len(), 3);
+        assert_eq!(
+            meals[0].foods[1].leftovers_of.as_ref().unwrap().slot,
+            "dinner"
+        );
+        assert_eq!(meals[1].headcount, Some(6));
+        assert!(meals[2].foods.is_empty());
+        assert_eq!(meals_to_db_string(meals.into_iter()).unwrap(), stored);
+    }
+
+    #[test]
+    fn empty_string_has_no_meals() {
+        assert!(db_string_to_meals("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn leftovers_of_invalid_slots_are_rejected() {
+        assert!(meals_to_db_string([leftovers("dinner")].into_iter()).is_ok());
+        assert!(meals_to_db_string([leftovers("din|ner")].into_iter()).is_err());
+        assert!(meals_to_db_string([leftovers("")].into_iter()).is_err());
+    }
+
+    #[test]
+    fn meals_of_removed_slots_are_kept() {
+        let mut meal = leftovers("brunch");
+        meal.slot = "brunch".to_string();
+
+        let stored = meals_to_db_string([meal].into_iter()).unwrap();
+        let meals = db_string_to_meals(&stored).unwrap();
+        assert_eq!(meals[0].slot, "brunch");
+        assert_eq!(
+            meals[0].foods[0].leftovers_of.as_ref().unwrap().slot,
+            "brunch"
+        );
+    }
+
+    #[test]
+    fn invalid_slots_are_rejected() {
+        let mut meal = leftovers("dinner");
+        meal.slot = "lunch=1".to_string();
+
+        assert!(meals_to_db_string([meal].into_iter()).is_err());
+    }
+
+    #[test]
+    fn moving_a_day_onto_itself_changes_nothing() {
+        let moved = moved_day(
+            date(1),
+            date(1),
+            vec![leftovers("dinner")],
+            &default_slots(),
+        );
+
+        assert!(moved.is_empty());
+    }
+
+    #[test]
+    fn moving_a_day_empties_the_source() {
+        let moved = moved_day(
+            date(1),
+            date(2),
+            vec![leftovers("dinner")],
+            &default_slots(),
+        );
+
+        assert_eq!(moved[0].0, date(2));
+        assert_eq!(moved[0].1.len(), 1);
+        assert_eq!(moved[1].0, date(1));
+        assert!(moved[1].1.iter().all(|m| m.foods.is_empty()));
+        assert_eq!(moved[1].1.len(), default_slots().len());
+    }
+
+    #[test]
+    fn leftovers_are_repointed_to_the_moved_meal() {
+        let mut meals = vec![leftovers("dinner"), leftovers("lunch")];
+        let to = MealRef {
+            date: date(5),
+            slot: "lunch".to_string(),
+        };
+        let changed = repoint_meals(&mut meals, |meal| {
+            (meal.date == date(1) && meal.slot == "dinner").then(|| to.clone())
+        });
+
+        assert!(changed);
+        assert_eq!(meals[0].foods[0].leftovers_of.as_ref(), Some(&to));
+        assert_eq!(
+            meals[1].foods[0].leftovers_of.as_ref().unwrap().date,
+            date(1)
+        );
+        assert!(!repoint_meals(&mut meals, |_| None));
+    }
+
+    #[test]
+    fn corrupt_meals_are_errors() {
+        assert!(db_string_to_meals("lunch").is_err());
+        assert!(db_string_to_meals("lunch=x,1").is_err());
+        assert!(db_string_to_meals("lunch=1").is_err());
+        assert!(db_string_to_meals("lunch=1,1,2025-03-01").is_err());
+        assert!(db_string_to_meals("lunch#x=1,1").is_err());
+    }
+}
@@ -26,8 +26,7 @@ pub async fn get_all_existing_tags(mut conn: Connection<'_>) -> Result<Vec<Strin
 
     let tags = rows
         .iter()
-        .map(|row| string_to_vec(&row.get::<_, String>("tags")))
-        .flatten()
+        .flat_map(|row| string_to_vec(&row.get::<_, String>("tags")))
         .collect::<HashSet<String>>();
 
     Ok(tags.into_iter().collect())
@@ -195,7 +194,7 @@ fn row_to_food(
     let id = food_row.get("id");
     let name: String = food_row.get("name");
     let portions = food_row.get("portions");
-    let tags: Vec<String> = string_to_vec(&food_row.get("tags"));
+    let tags: Vec<String> = string_to_vec(food_row.get("tags"));
     let details: String = food_row.get("details");
 
     let ingredients = rows_to_ingredients(ingredient_rows)?;
@@ -237,7 +236,7 @@ fn rows_to_ingredients(rows: impl Iterator<Item = tokio_postgres::Row>) -> Resul
 
 /// Fetches all foods with the given ids.
 pub async fn get_foods_in(tx: &Transaction<'_>, ids: &[i32]) -> Result<Vec<Food>> {
-    let (in_clause, params) = match build_in_clause(&ids) {
+    let (in_clause, params) = match build_in_clause(ids) {
         None => return Ok(Vec::new()),
         Some(in_clause) => in_clause,
     };
@@ -0,0 +1,74 @@
+use crate::db::Connection;
+use crate::household::Member;
+use crate::utils::{string_to_vec, vec_to_string};
+use anyhow::Result;
+
+/// Fetches all household members.
+pub async fn get_members(mut conn: Connection<'_>) -> Result<Vec<Member>> {
+    let tx = conn.transaction().await?;
+
+    let rows = tx
+        .query("SELECT * FROM household_member ORDER BY id ASC", &[])
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Member {
+            id: Some(row.get("id")),
+            name: row.get("name"),
+            forbidden_tags: string_to_vec(row.get("forbidden_tags")),
+            forbidden_ingredients: string_to_vec(row.get("forbidden_ingredients")),
+            slots: string_to_vec(row.get("slots")),
+        })
+        .collect())
+}
+
+/// This can update an existing member or create a new one.
+pub async fn update_member(mut conn: Connection<'_>, member: &Member) -> Result<i32> {
+    if member.name.trim().is_empty() {
+        return Err(anyhow::anyhow!("The member needs a name"));
+    }
+
+    let forbidden_tags = vec_to_string(&member.forbidden_tags);
+    let forbidden_ingredients = vec_to_string(&member.forbidden_ingredients);
+    let slots = vec_to_string(&member.slots);
+
+    let tx = conn.transaction().await?;
+
+    let id = match member.id {
+        Some(id) => {
+            tx.execute(
+                "UPDATE household_member SET name = $2, forbidden_tags = $3, forbidden_ingredients = $4, slots = $5 WHERE id = $1",
+                &[&id, &member.name, &forbidden_tags, &forbidden_ingredients, &slots],
+            )
+            .await?;
+
+            id
+        }
+        None => tx
+            .query_one(
+                "INSERT INTO household_member (name, forbidden_tags, forbidden_ingredients, slots) VALUES ($1, $2, $3, $4) RETURNING id",
+                &[&member.name, &forbidden_tags, &forbidden_ingredients, &slots],
+            )
+            .await?
+            .get("id"),
+    };
+
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// This removes a member from the database.
+pub async fn delete_member(mut conn: Connection<'_>, id: i32) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute("DELETE FROM household_member WHERE id = $1", &[&id])
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
@@ -6,7 +6,10 @@ use tokio_postgres::NoTls;
 
 pub mod calendar;
 pub mod food;
+pub mod household;
+pub mod recurring;
 pub mod shopping;
+pub mod week_template;
 
 pub type ConnectionPool = bb8::Pool<PostgresConnectionManager<NoTls>>;
 pub type Connection<'a> = bb8::PooledConnection<'a, PostgresConnectionManager<NoTls>>;
@@ -38,3 +41,138 @@ pub async fn generate_pool(config: &Config) -> Result<ConnectionPool> {
 
     Ok(pool)
 }
+
+/// Statements that bring an existing database to the current schema.
+/// They run on every start, so every statement has to be idempotent.
+const MIGRATIONS: &[&str] = &[
+    // Meals are stored per configurable slot instead of fixed lunch and dinner columns
+    "ALTER TABLE day ADD COLUMN IF NOT EXISTS meals TEXT NOT NULL DEFAULT ''",
+    "DO $$ BEGIN
+        IF EXISTS (
+            SELECT 1 FROM information_schema.columns WHERE table_name = 'day' AND column_name = 'lunch'
+        ) THEN
+            UPDATE day SET meals = 'lunch=' || lunch || '|dinner=' || dinner;
+            ALTER TABLE day DROP COLUMN lunch;
+            ALTER TABLE day DROP COLUMN dinner;
+        END IF;
+    END $$",
+    "CREATE TABLE IF NOT EXISTS recurring_rule (
+        id SERIAL PRIMARY KEY,
+        weekday INTEGER,
+        interval_days INTEGER,
+        slot TEXT NOT NULL,
+        food_id INTEGER,
+        tag TEXT,
+        factor REAL NOT NULL,
+        start_date DATE NOT NULL,
+        end_date DATE
+    )",
+    "CREATE TABLE IF NOT EXISTS week_template (
+        id SERIAL PRIMARY KEY,
+        name TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS week_template_day (
+        template_id INTEGER NOT NULL REFERENCES week_template (id) ON DELETE CASCADE,
+        weekday INTEGER NOT NULL,
+        meals TEXT NOT NULL,
+        PRIMARY KEY (template_id, weekday)
+    )",
+    "ALTER TABLE day ADD COLUMN IF NOT EXISTS note TEXT NOT NULL DEFAULT ''",
+    "CREATE TABLE IF NOT EXISTS household_member (
+        id SERIAL PRIMARY KEY,
+        name TEXT NOT NULL,
+        forbidden_tags TEXT NOT NULL DEFAULT '',
+        forbidden_ingredients TEXT NOT NULL DEFAULT '',
+        slots TEXT NOT NULL DEFAULT ''
+    )",
+    "CREATE TABLE IF NOT EXISTS shopping_list (
+        id SERIAL PRIMARY KEY,
+        from_date DATE NOT NULL,
+        to_date DATE NOT NULL,
+        created_at TIMESTAMP NOT NULL,
+        default_items BOOLEAN NOT NULL,
+        pushed_at TIMESTAMP,
+        pushed_to TEXT
+    )",
+    "CREATE TABLE IF NOT EXISTS shopping_list_item (
+        list_id INTEGER NOT NULL REFERENCES shopping_list (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        amount TEXT,
+        checked BOOLEAN NOT NULL,
+        optional BOOLEAN NOT NULL,
+        PRIMARY KEY (list_id, position)
+    )",
+    "ALTER TABLE shopping_list ADD COLUMN IF NOT EXISTS skipped_dates TEXT NOT NULL DEFAULT ''",
+    "CREATE TABLE IF NOT EXISTS shopping_list_item_source (
+        list_id INTEGER NOT NULL,
+        item_position INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        food TEXT NOT NULL,
+        date DATE NOT NULL,
+        slot TEXT NOT NULL,
+        amount TEXT,
+        PRIMARY KEY (list_id, item_position, position),
+        FOREIGN KEY (list_id, item_position) REFERENCES shopping_list_item (list_id, position) ON DELETE CASCADE
+    )",
+    "ALTER TABLE shopping_list_item ADD COLUMN IF NOT EXISTS section TEXT",
+    "CREATE TABLE IF NOT EXISTS default_list (
+        id SERIAL PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    )",
+    // The items of the former single default list are moved to a named list
+    "DO $$ BEGIN
+        IF NOT EXISTS (
+            SELECT 1 FROM information_schema.columns WHERE table_name = 'item' AND column_name = 'list_id'
+        ) THEN
+            ALTER TABLE item ADD COLUMN list_id INTEGER REFERENCES default_list (id) ON DELETE CASCADE;
+            IF EXISTS (SELECT 1 FROM item) THEN
+                INSERT INTO default_list (name) VALUES ('Standard Einkauf');
+                UPDATE item SET list_id = (SELECT id FROM default_list WHERE name = 'Standard Einkauf');
+            END IF;
+            ALTER TABLE item ALTER COLUMN list_id SET NOT NULL;
+        END IF;
+    END $$",
+    "DO $$ BEGIN
+        IF EXISTS (
+            SELECT 1 FROM information_schema.columns WHERE table_name = 'shopping_list' AND column_name = 'default_items'
+        ) THEN
+            ALTER TABLE shopping_list ADD COLUMN default_lists TEXT NOT NULL DEFAULT '';
+            UPDATE shopping_list SET default_lists = 'Standard Einkauf' WHERE default_items;
+            ALTER TABLE shopping_list DROP COLUMN default_items;
+        END IF;
+    END $$",
+    "ALTER TABLE item ADD COLUMN IF NOT EXISTS interval_days INTEGER",
+    "ALTER TABLE item ADD COLUMN IF NOT EXISTS last_added DATE",
+    "ALTER TABLE shopping_list_item ADD COLUMN IF NOT EXISTS default_ids TEXT NOT NULL DEFAULT ''",
+    "CREATE TABLE IF NOT EXISTS ingredient_alias (
+        alias TEXT PRIMARY KEY,
+        name TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS package (
+        ingredient TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        size REAL NOT NULL,
+        unit TEXT NOT NULL
+    )",
+    "ALTER TABLE shopping_list ADD COLUMN IF NOT EXISTS round_packages BOOLEAN NOT NULL DEFAULT FALSE",
+    "ALTER TABLE shopping_list_item ADD COLUMN IF NOT EXISTS needed TEXT",
+    "CREATE TABLE IF NOT EXISTS section_override (
+        ingredient TEXT PRIMARY KEY,
+        section TEXT NOT NULL
+    )",
+];
+
+/// Applies all migrations to the database.
+pub async fn migrate(pool: &ConnectionPool) -> Result<()> {
+    let mut conn = pool.get().await?;
+    let tx = conn.transaction().await?;
+
+    for migration in MIGRATIONS {
+        tx.batch_execute(migration).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
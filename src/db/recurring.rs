@@ -0,0 +1,118 @@
+use crate::calendar::{MealSlot, validate_slot};
+use crate::db::Connection;
+use crate::recurring::RecurringRule;
+use anyhow::Result;
+use chrono::{NaiveDate, Weekday};
+use tokio_postgres::Transaction;
+
+/// Fetches all recurring rules.
+pub async fn get_rules(mut conn: Connection<'_>) -> Result<Vec<RecurringRule>> {
+    let tx = conn.transaction().await?;
+    let rules = query_rules(&tx).await?;
+    tx.commit().await?;
+
+    Ok(rules)
+}
+
+/// Fetches all recurring rules within an existing transaction.
+pub async fn query_rules(tx: &Transaction<'_>) -> Result<Vec<RecurringRule>> {
+    let rows = tx
+        .query("SELECT * FROM recurring_rule ORDER BY id ASC", &[])
+        .await?;
+
+    rows.iter().map(row_to_rule).collect()
+}
+
+/// This can update an existing rule or create a new one.
+pub async fn update_rule(
+    mut conn: Connection<'_>,
+    rule: &RecurringRule,
+    slots: &[MealSlot],
+) -> Result<i32> {
+    validate_slot(slots, &rule.slot)?;
+    if rule.weekday.is_none() && rule.interval_days.is_none_or(|i| i <= 0) {
+        return Err(anyhow::anyhow!("Either a weekday or an interval is needed"));
+    }
+    if rule.food_id.is_none() && rule.tag.is_none() {
+        return Err(anyhow::anyhow!("Either a food or a tag is needed"));
+    }
+
+    let weekday = rule.weekday.map(|w| w.num_days_from_monday() as i32);
+
+    let tx = conn.transaction().await?;
+
+    let id = match rule.id {
+        Some(id) => {
+            tx.execute(
+                "UPDATE recurring_rule SET weekday = $2, interval_days = $3, slot = $4, food_id = $5, tag = $6, factor = $7, start_date = $8, end_date = $9 WHERE id = $1",
+                &[
+                    &id,
+                    &weekday,
+                    &rule.interval_days,
+                    &rule.slot,
+                    &rule.food_id,
+                    &rule.tag,
+                    &rule.factor,
+                    &rule.start,
+                    &rule.end,
+                ],
+            )
+            .await?;
+
+            id
+        }
+        None => tx
+            .query_one(
+                "INSERT INTO recurring_rule (weekday, interval_days, slot, food_id, tag, factor, start_date, end_date) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+                &[
+                    &weekday,
+                    &rule.interval_days,
+                    &rule.slot,
+                    &rule.food_id,
+                    &rule.tag,
+                    &rule.factor,
+                    &rule.start,
+                    &rule.end,
+                ],
+            )
+            .await?
+            .get("id"),
+    };
+
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// This removes a rule from the database.
+pub async fn delete_rule(mut conn: Connection<'_>, id: i32) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute("DELETE FROM recurring_rule WHERE id = $1", &[&id])
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+fn row_to_rule(row: &tokio_postgres::Row) -> Result<RecurringRule> {
+    let weekday = row
+        .get::<_, Option<i32>>("weekday")
+        .map(|w| Weekday::try_from(w as u8))
+        .transpose()?;
+    let start: NaiveDate = row.get("start_date");
+    let end: Option<NaiveDate> = row.get("end_date");
+
+    Ok(RecurringRule {
+        id: Some(row.get("id")),
+        weekday,
+        interval_days: row.get("interval_days"),
+        slot: row.get("slot"),
+        food_id: row.get("food_id"),
+        tag: row.get("tag"),
+        factor: row.get("factor"),
+        start,
+        end,
+    })
+}
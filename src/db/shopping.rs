@@ -1,22 +1,134 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::db::Connection;
-use crate::shopping::{EditItem, Item};
-use crate::utils::{amount_unit_to_string, string_to_amount_unit};
+use crate::names::Alias;
+use crate::shopping::{
+    DefaultList, EditItem, Item, ListItem, Package, SectionOverride, ShoppingList, Source,
+};
+use crate::utils::{amount_unit_to_string, string_to_amount_unit, string_to_vec, vec_to_string};
 use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime};
+use tokio_postgres::Transaction;
 
-/// This function retrieves all items from the database.
-pub async fn get_items(mut conn: Connection<'_>) -> Result<Vec<Item>> {
+/// Fetches all default lists with their items.
+pub async fn get_default_lists(mut conn: Connection<'_>) -> Result<Vec<DefaultList>> {
     let tx = conn.transaction().await?;
+
+    let rows = tx
+        .query("SELECT * FROM default_list ORDER BY name ASC", &[])
+        .await?;
     let item_rows = tx
         .query("SELECT * FROM item ORDER BY ordering ASC", &[])
         .await?;
     tx.commit().await?;
 
-    let items = rows_to_items(item_rows)?;
+    let mut items: HashMap<i32, Vec<Item>> = HashMap::new();
+    for (list_id, item) in rows_to_items(item_rows)? {
+        items.entry(list_id).or_default().push(item);
+    }
+
+    let lists = rows
+        .iter()
+        .map(|row| {
+            let id: i32 = row.get("id");
+            DefaultList {
+                id: Some(id),
+                name: row.get("name"),
+                items: items.remove(&id).unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    Ok(lists)
+}
+
+/// Fetches a single default list with its items.
+pub async fn get_default_list(mut conn: Connection<'_>, id: i32) -> Result<DefaultList> {
+    let tx = conn.transaction().await?;
+
+    let row = tx
+        .query_one("SELECT * FROM default_list WHERE id = $1", &[&id])
+        .await?;
+    let item_rows = tx
+        .query(
+            "SELECT * FROM item WHERE list_id = $1 ORDER BY ordering ASC",
+            &[&id],
+        )
+        .await?;
+    tx.commit().await?;
+
+    Ok(DefaultList {
+        id: Some(id),
+        name: row.get("name"),
+        items: rows_to_items(item_rows)?
+            .into_iter()
+            .map(|(_, item)| item)
+            .collect(),
+    })
+}
+
+/// Creates an empty default list and returns its id.
+pub async fn create_default_list(mut conn: Connection<'_>, name: &str) -> Result<i32> {
+    let tx = conn.transaction().await?;
+
+    let id: i32 = tx
+        .query_one(
+            "INSERT INTO default_list (name) VALUES ($1) RETURNING id",
+            &[&name],
+        )
+        .await?
+        .get("id");
+
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// This removes a default list with all its items.
+pub async fn delete_default_list(mut conn: Connection<'_>, id: i32) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute("DELETE FROM default_list WHERE id = $1", &[&id])
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// This function retrieves the items of the given default lists from the database.
+pub async fn get_items(mut conn: Connection<'_>, list_ids: &[i32]) -> Result<Vec<Item>> {
+    let tx = conn.transaction().await?;
+    let item_rows = tx
+        .query(
+            "SELECT * FROM item WHERE list_id = ANY($1) ORDER BY list_id ASC, ordering ASC",
+            &[&list_ids],
+        )
+        .await?;
+    tx.commit().await?;
+
+    let items = rows_to_items(item_rows)?
+        .into_iter()
+        .map(|(_, item)| item)
+        .collect();
     Ok(items)
 }
 
+/// Records that the default items were added to a shopping list on this date.
+pub async fn set_last_added(mut conn: Connection<'_>, ids: &[i32], date: NaiveDate) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute(
+        "UPDATE item SET last_added = $2 WHERE id = ANY($1)",
+        &[&ids, &date],
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
 /// This removes an item from the database.
 pub async fn delete_item(mut conn: Connection<'_>, id: i32) -> Result<()> {
     let tx = conn.transaction().await?;
@@ -28,13 +140,24 @@ pub async fn delete_item(mut conn: Connection<'_>, id: i32) -> Result<()> {
     Ok(())
 }
 
-/// This can update existing items or create new ones.
-pub async fn update_items(mut conn: Connection<'_>, items: &Vec<EditItem>) -> Result<()> {
+/// This renames a default list and updates its existing items or creates new ones.
+pub async fn update_default_list(
+    mut conn: Connection<'_>,
+    id: i32,
+    name: &str,
+    items: &Vec<EditItem>,
+) -> Result<()> {
     let tx = conn.transaction().await?;
 
+    tx.execute(
+        "UPDATE default_list SET name = $2 WHERE id = $1",
+        &[&id, &name],
+    )
+    .await?;
+
     // Fetch all the old item ids to be able to remove the ones that are no longer used.
     let mut old_item_ids: HashSet<i32> = tx
-        .query("SELECT id FROM item", &[])
+        .query("SELECT id FROM item WHERE list_id = $1", &[&id])
         .await?
         .iter()
         .map(|row| row.get("id"))
@@ -42,26 +165,30 @@ pub async fn update_items(mut conn: Connection<'_>, items: &Vec<EditItem>) -> Re
 
     for item in items {
         match item.id {
-            Some(id) => {
-                old_item_ids.remove(&id);
+            Some(item_id) => {
+                old_item_ids.remove(&item_id);
                 tx.execute(
-                    "UPDATE item SET name = $2, amount = $3, ordering = $4 WHERE id = $1",
+                    "UPDATE item SET name = $2, amount = $3, ordering = $4, interval_days = $6 WHERE id = $1 AND list_id = $5",
                     &[
-                        &id,
+                        &item_id,
                         &item.name,
                         &amount_unit_to_string(&item.amount),
                         &item.order,
+                        &id,
+                        &item.interval_days,
                     ],
                 )
                 .await?;
             }
             None => {
                 tx.execute(
-                    "INSERT INTO item (name, amount, ordering) VALUES ($1, $2, $3)",
+                    "INSERT INTO item (name, amount, ordering, list_id, interval_days) VALUES ($1, $2, $3, $4, $5)",
                     &[
                         &item.name,
                         &amount_unit_to_string(&item.amount),
                         &item.order,
+                        &id,
+                        &item.interval_days,
                     ],
                 )
                 .await?;
@@ -70,8 +197,9 @@ pub async fn update_items(mut conn: Connection<'_>, items: &Vec<EditItem>) -> Re
     }
 
     // Remove the items that are no longer used.
-    for id in old_item_ids {
-        tx.execute("DELETE FROM item WHERE id = $1", &[&id]).await?;
+    for item_id in old_item_ids {
+        tx.execute("DELETE FROM item WHERE id = $1", &[&item_id])
+            .await?;
     }
 
     tx.commit().await?;
@@ -79,12 +207,14 @@ pub async fn update_items(mut conn: Connection<'_>, items: &Vec<EditItem>) -> Re
     Ok(())
 }
 
-/// This function is used to convert rows from the database into a Vec of Item structs.
-fn rows_to_items(rows: Vec<tokio_postgres::Row>) -> Result<Vec<Item>> {
+/// This function is used to convert rows from the database into Item structs.
+/// Every item is returned with the id of the default list it belongs to.
+fn rows_to_items(rows: Vec<tokio_postgres::Row>) -> Result<Vec<(i32, Item)>> {
     let mut items = Vec::new();
 
     for row in rows {
         let id: i32 = row.get("id");
+        let list_id: i32 = row.get("list_id");
         let name: String = row.get("name");
         let amount_str = row.get::<_, Option<String>>("amount");
         let amount = string_to_amount_unit(&amount_str)?;
@@ -95,9 +225,433 @@ fn rows_to_items(rows: Vec<tokio_postgres::Row>) -> Result<Vec<Item>> {
             name,
             amount,
             order,
+            source: None,
+            interval_days: row.get("interval_days"),
+            last_added: row.get("last_added"),
         };
-        items.push(item);
+        items.push((list_id, item));
     }
 
     Ok(items)
 }
+
+/// Fetches all stored shopping lists, the newest first.
+pub async fn get_lists(mut conn: Connection<'_>) -> Result<Vec<ShoppingList>> {
+    let tx = conn.transaction().await?;
+
+    let rows = tx
+        .query("SELECT * FROM shopping_list ORDER BY created_at DESC", &[])
+        .await?;
+
+    let mut lists = Vec::new();
+    for row in rows {
+        let items = get_list_items(&tx, row.get("id")).await?;
+        lists.push(row_to_list(&row, items)?);
+    }
+
+    tx.commit().await?;
+
+    Ok(lists)
+}
+
+/// Fetches a single stored shopping list with its items.
+pub async fn get_list(mut conn: Connection<'_>, id: i32) -> Result<ShoppingList> {
+    let tx = conn.transaction().await?;
+
+    let row = tx
+        .query_one("SELECT * FROM shopping_list WHERE id = $1", &[&id])
+        .await?;
+    let items = get_list_items(&tx, id).await?;
+
+    tx.commit().await?;
+
+    row_to_list(&row, items)
+}
+
+/// Stores a new shopping list and returns its id.
+pub async fn create_list(mut conn: Connection<'_>, list: &ShoppingList) -> Result<i32> {
+    let skipped: Vec<String> = list.skipped.iter().map(|d| d.to_string()).collect();
+
+    let tx = conn.transaction().await?;
+
+    let id: i32 = tx
+        .query_one(
+            "INSERT INTO shopping_list (from_date, to_date, skipped_dates, created_at, default_lists, round_packages) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+            &[
+                &list.from,
+                &list.to,
+                &vec_to_string(&skipped),
+                &list.created_at,
+                &vec_to_string(&list.default_lists),
+                &list.round_packages,
+            ],
+        )
+        .await?
+        .get("id");
+    insert_list_items(&tx, id, &list.items).await?;
+
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// Fetches the ranges of all pushed shopping lists which overlap with `from` until `to`
+/// (inclusive). The days in these ranges were shopped for already.
+pub async fn get_covered_ranges(
+    mut conn: Connection<'_>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<(NaiveDate, NaiveDate)>> {
+    let tx = conn.transaction().await?;
+
+    let rows = tx
+        .query(
+            "SELECT from_date, to_date FROM shopping_list WHERE pushed_at IS NOT NULL AND from_date <= $2 AND to_date >= $1 ORDER BY from_date ASC",
+            &[&from, &to],
+        )
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| (row.get("from_date"), row.get("to_date")))
+        .collect())
+}
+
+/// Replaces the items of a stored shopping list.
+pub async fn update_list_items(
+    mut conn: Connection<'_>,
+    id: i32,
+    items: &[ListItem],
+) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute("DELETE FROM shopping_list_item WHERE list_id = $1", &[&id])
+        .await?;
+    insert_list_items(&tx, id, items).await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Stores whether the amounts of a shopping list are rounded up to the package sizes.
+pub async fn set_list_round_packages(
+    mut conn: Connection<'_>,
+    id: i32,
+    round_packages: bool,
+) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute(
+        "UPDATE shopping_list SET round_packages = $2 WHERE id = $1",
+        &[&id, &round_packages],
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Marks a shopping list as pushed to the Bring list with the given name.
+pub async fn set_list_pushed(
+    mut conn: Connection<'_>,
+    id: i32,
+    pushed_at: NaiveDateTime,
+    pushed_to: &str,
+) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute(
+        "UPDATE shopping_list SET pushed_at = $2, pushed_to = $3 WHERE id = $1",
+        &[&id, &pushed_at, &pushed_to],
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// This removes a stored shopping list and its items.
+pub async fn delete_list(mut conn: Connection<'_>, id: i32) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute("DELETE FROM shopping_list WHERE id = $1", &[&id])
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Fetches the manually assigned sections of all ingredients.
+pub async fn get_section_overrides(mut conn: Connection<'_>) -> Result<Vec<SectionOverride>> {
+    let tx = conn.transaction().await?;
+
+    let rows = tx
+        .query(
+            "SELECT * FROM section_override ORDER BY ingredient ASC",
+            &[],
+        )
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| SectionOverride {
+            ingredient: row.get("ingredient"),
+            section: row.get("section"),
+        })
+        .collect())
+}
+
+/// Assigns a section to an ingredient or replaces the assigned one.
+/// The ingredient is stored in lowercase, so the override applies regardless of the case.
+pub async fn set_section_override(
+    mut conn: Connection<'_>,
+    section_override: &SectionOverride,
+) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute(
+        "INSERT INTO section_override (ingredient, section) VALUES ($1, $2) ON CONFLICT (ingredient) DO UPDATE SET section = $2",
+        &[
+            &section_override.ingredient.trim().to_lowercase(),
+            &section_override.section,
+        ],
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// This removes the manually assigned section of an ingredient.
+pub async fn delete_section_override(mut conn: Connection<'_>, ingredient: &str) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute(
+        "DELETE FROM section_override WHERE ingredient = $1",
+        &[&ingredient.trim().to_lowercase()],
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Fetches all defined packages.
+pub async fn get_packages(mut conn: Connection<'_>) -> Result<Vec<Package>> {
+    let tx = conn.transaction().await?;
+
+    let rows = tx
+        .query("SELECT * FROM package ORDER BY ingredient ASC", &[])
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Package {
+            ingredient: row.get("ingredient"),
+            name: row.get("name"),
+            size: row.get("size"),
+            unit: row.get("unit"),
+        })
+        .collect())
+}
+
+/// Defines the package of an ingredient or replaces the defined one.
+pub async fn set_package(mut conn: Connection<'_>, package: &Package) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute(
+        "INSERT INTO package (ingredient, name, size, unit) VALUES ($1, $2, $3, $4) ON CONFLICT (ingredient) DO UPDATE SET name = $2, size = $3, unit = $4",
+        &[
+            &package.ingredient.trim(),
+            &package.name.trim(),
+            &package.size,
+            &package.unit.trim(),
+        ],
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// This removes the package of an ingredient.
+pub async fn delete_package(mut conn: Connection<'_>, ingredient: &str) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute("DELETE FROM package WHERE ingredient = $1", &[&ingredient])
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Fetches all aliases of ingredient names.
+pub async fn get_aliases(mut conn: Connection<'_>) -> Result<Vec<Alias>> {
+    let tx = conn.transaction().await?;
+
+    let rows = tx
+        .query(
+            "SELECT * FROM ingredient_alias ORDER BY name ASC, alias ASC",
+            &[],
+        )
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Alias {
+            alias: row.get("alias"),
+            name: row.get("name"),
+        })
+        .collect())
+}
+
+/// Stores an alias or replaces the name of an existing one.
+pub async fn set_alias(mut conn: Connection<'_>, alias: &Alias) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute(
+        "INSERT INTO ingredient_alias (alias, name) VALUES ($1, $2) ON CONFLICT (alias) DO UPDATE SET name = $2",
+        &[&alias.alias.trim(), &alias.name.trim()],
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// This removes an alias.
+pub async fn delete_alias(mut conn: Connection<'_>, alias: &str) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute("DELETE FROM ingredient_alias WHERE alias = $1", &[&alias])
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+async fn get_list_items(tx: &Transaction<'_>, id: i32) -> Result<Vec<ListItem>> {
+    let rows = tx
+        .query(
+            "SELECT * FROM shopping_list_item WHERE list_id = $1 ORDER BY position ASC",
+            &[&id],
+        )
+        .await?;
+
+    let mut items: Vec<ListItem> = rows
+        .iter()
+        .map(|row| ListItem {
+            name: row.get("name"),
+            amount: row.get("amount"),
+            checked: row.get("checked"),
+            optional: row.get("optional"),
+            sources: Vec::new(),
+            section: row.get("section"),
+            default_ids: string_to_vec(row.get("default_ids"))
+                .iter()
+                .filter_map(|id| id.parse().ok())
+                .collect(),
+            needed: row.get("needed"),
+        })
+        .collect();
+
+    let source_rows = tx
+        .query(
+            "SELECT * FROM shopping_list_item_source WHERE list_id = $1 ORDER BY item_position ASC, position ASC",
+            &[&id],
+        )
+        .await?;
+
+    for row in source_rows {
+        let item_position: i32 = row.get("item_position");
+        let Some(item) = items.get_mut(item_position as usize) else {
+            continue;
+        };
+
+        item.sources.push(Source {
+            food: row.get("food"),
+            date: row.get("date"),
+            slot: row.get("slot"),
+            amount: string_to_amount_unit(&row.get("amount"))?,
+        });
+    }
+
+    Ok(items)
+}
+
+async fn insert_list_items(tx: &Transaction<'_>, id: i32, items: &[ListItem]) -> Result<()> {
+    for (position, item) in items.iter().enumerate() {
+        let default_ids: Vec<String> = item.default_ids.iter().map(|id| id.to_string()).collect();
+        tx.execute(
+            "INSERT INTO shopping_list_item (list_id, position, name, amount, checked, optional, section, default_ids, needed) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+            &[
+                &id,
+                &(position as i32),
+                &item.name,
+                &item.amount,
+                &item.checked,
+                &item.optional,
+                &item.section,
+                &vec_to_string(&default_ids),
+                &item.needed,
+            ],
+        )
+        .await?;
+
+        for (source_position, source) in item.sources.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO shopping_list_item_source (list_id, item_position, position, food, date, slot, amount) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                &[
+                    &id,
+                    &(position as i32),
+                    &(source_position as i32),
+                    &source.food,
+                    &source.date,
+                    &source.slot,
+                    &amount_unit_to_string(&source.amount),
+                ],
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn row_to_list(row: &tokio_postgres::Row, items: Vec<ListItem>) -> Result<ShoppingList> {
+    let skipped = string_to_vec(row.get("skipped_dates"))
+        .iter()
+        .map(|d| d.parse())
+        .collect::<Result<_, _>>()?;
+
+    Ok(ShoppingList {
+        id: Some(row.get("id")),
+        from: row.get("from_date"),
+        to: row.get("to_date"),
+        skipped,
+        created_at: row.get("created_at"),
+        default_lists: string_to_vec(row.get("default_lists")),
+        items,
+        pushed_at: row.get("pushed_at"),
+        pushed_to: row.get("pushed_to"),
+        round_packages: row.get("round_packages"),
+    })
+}
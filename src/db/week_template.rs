@@ -0,0 +1,123 @@
+use super::calendar::{db_string_to_meals, meals_to_db_string};
+use crate::db::Connection;
+use crate::week_template::{TemplateDay, WeekTemplate};
+use anyhow::Result;
+use chrono::Weekday;
+use tokio_postgres::Transaction;
+
+/// Fetches all week templates with their days.
+pub async fn get_templates(mut conn: Connection<'_>) -> Result<Vec<WeekTemplate>> {
+    let tx = conn.transaction().await?;
+
+    let rows = tx
+        .query("SELECT id, name FROM week_template ORDER BY name ASC", &[])
+        .await?;
+
+    let mut templates = Vec::new();
+    for row in rows {
+        let id: i32 = row.get("id");
+        templates.push(WeekTemplate {
+            id: Some(id),
+            name: row.get("name"),
+            days: get_template_days(&tx, id).await?,
+        });
+    }
+
+    tx.commit().await?;
+
+    Ok(templates)
+}
+
+/// Fetches a single week template with its days.
+pub async fn get_template(mut conn: Connection<'_>, id: i32) -> Result<WeekTemplate> {
+    let tx = conn.transaction().await?;
+
+    let row = tx
+        .query_one("SELECT id, name FROM week_template WHERE id = $1", &[&id])
+        .await?;
+    let template = WeekTemplate {
+        id: Some(id),
+        name: row.get("name"),
+        days: get_template_days(&tx, id).await?,
+    };
+
+    tx.commit().await?;
+
+    Ok(template)
+}
+
+/// Stores a template. A template with the same name gets replaced.
+pub async fn save_template(mut conn: Connection<'_>, template: WeekTemplate) -> Result<i32> {
+    let tx = conn.transaction().await?;
+
+    let existing = tx
+        .query_opt(
+            "SELECT id FROM week_template WHERE name = $1",
+            &[&template.name],
+        )
+        .await?;
+
+    let id: i32 = match existing {
+        Some(row) => {
+            let id = row.get("id");
+            tx.execute(
+                "DELETE FROM week_template_day WHERE template_id = $1",
+                &[&id],
+            )
+            .await?;
+            id
+        }
+        None => tx
+            .query_one(
+                "INSERT INTO week_template (name) VALUES ($1) RETURNING id",
+                &[&template.name],
+            )
+            .await?
+            .get("id"),
+    };
+
+    for day in template.days {
+        let weekday = day.weekday.num_days_from_monday() as i32;
+        let meals = meals_to_db_string(day.meals.into_iter())?;
+        tx.execute(
+            "INSERT INTO week_template_day (template_id, weekday, meals) VALUES ($1, $2, $3)",
+            &[&id, &weekday, &meals],
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// This removes a template and its days from the database.
+pub async fn delete_template(mut conn: Connection<'_>, id: i32) -> Result<()> {
+    let tx = conn.transaction().await?;
+
+    tx.execute("DELETE FROM week_template WHERE id = $1", &[&id])
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+async fn get_template_days(tx: &Transaction<'_>, id: i32) -> Result<Vec<TemplateDay>> {
+    let rows = tx
+        .query(
+            "SELECT weekday, meals FROM week_template_day WHERE template_id = $1 ORDER BY weekday ASC",
+            &[&id],
+        )
+        .await?;
+
+    rows.iter()
+        .map(|row| {
+            let weekday: i32 = row.get("weekday");
+            Ok(TemplateDay {
+                weekday: Weekday::try_from(weekday as u8)?,
+                meals: db_string_to_meals(row.get("meals"))?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,218 @@
+use crate::shopping::{ListItem, ShoppingList};
+use serde::{Deserialize, Serialize};
+
+/// The formats a shopping list can be exported to, for everyone who does not use Bring!.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Plain text which can be pasted into a messenger.
+    #[default]
+    Text,
+    /// A Markdown checklist.
+    Markdown,
+    Csv,
+    Json,
+}
+
+impl Format {
+    /// Picks the first supported format of an `Accept` header.
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        accept.split(',').find_map(|media_type| {
+            match media_type.split(';').next().unwrap_or("").trim() {
+                "text/plain" => Some(Format::Text),
+                "text/markdown" => Some(Format::Markdown),
+                "text/csv" => Some(Format::Csv),
+                "application/json" => Some(Format::Json),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Text => "text/plain; charset=utf-8",
+            Format::Markdown => "text/markdown; charset=utf-8",
+            Format::Csv => "text/csv; charset=utf-8",
+            Format::Json => "application/json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Markdown => "md",
+            Format::Csv => "csv",
+            Format::Json => "json",
+        }
+    }
+}
+
+/// An exported item, the JSON export consists of these.
+#[derive(Serialize)]
+struct ExportItem<'a> {
+    name: &'a str,
+    amount: Option<&'a str>,
+    section: Option<&'a str>,
+}
+
+/// Exports the checked items of the list, which are the ones that would be pushed to Bring!.
+/// The items keep the order of the list, so they stay grouped by section.
+pub fn render(list: &ShoppingList, format: Format) -> String {
+    let items: Vec<&ListItem> = list.items.iter().filter(|i| i.checked).collect();
+    let title = format!(
+        "Einkaufsliste {} – {}",
+        list.from.format("%d.%m.%Y"),
+        list.to.format("%d.%m.%Y")
+    );
+
+    match format {
+        Format::Text => lines(&title, &items, "", "- "),
+        Format::Markdown => lines(&format!("# {}", title), &items, "## ", "- [ ] "),
+        Format::Csv => csv(&items),
+        Format::Json => {
+            let items: Vec<ExportItem> = items
+                .iter()
+                .map(|item| ExportItem {
+                    name: &item.name,
+                    amount: item.amount.as_deref(),
+                    section: item.section.as_deref(),
+                })
+                .collect();
+            serde_json::json!({
+                "from": list.from,
+                "to": list.to,
+                "items": items,
+            })
+            .to_string()
+        }
+    }
+}
+
+/// Writes one line per item and a heading whenever a new section starts.
+fn lines(title: &str, items: &[&ListItem], heading: &str, bullet: &str) -> String {
+    let mut text = format!("{}\n", title);
+    let mut section = None;
+
+    for (index, item) in items.iter().enumerate() {
+        if index == 0 || item.section != section {
+            section = item.section.clone();
+            text.push('\n');
+            if let Some(name) = &section {
+                text.push_str(&format!("{}{}\n", heading, name));
+            }
+        }
+
+        text.push_str(bullet);
+        text.push_str(&item_label(item));
+        text.push('\n');
+    }
+
+    text
+}
+
+fn item_label(item: &ListItem) -> String {
+    match &item.amount {
+        Some(amount) if !amount.is_empty() => format!("{} ({})", item.name, amount),
+        _ => item.name.clone(),
+    }
+}
+
+fn csv(items: &[&ListItem]) -> String {
+    let mut text = String::from("Name,Menge,Abteilung\n");
+
+    for item in items {
+        let fields = [
+            item.name.as_str(),
+            item.amount.as_deref().unwrap_or(""),
+            item.section.as_deref().unwrap_or(""),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        text.push_str(&fields.join(","));
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Quotes the field if it contains a separator, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn item(name: &str, amount: Option<&str>, section: Option<&str>, checked: bool) -> ListItem {
+        ListItem {
+            name: name.to_string(),
+            amount: amount.map(str::to_string),
+            checked,
+            optional: false,
+            sources: Vec::new(),
+            section: section.map(str::to_string),
+            default_ids: Vec::new(),
+            needed: None,
+        }
+    }
+
+    fn list() -> ShoppingList {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        ShoppingList {
+            id: Some(1),
+            from: date,
+            to: date + chrono::Days::new(6),
+            skipped: Vec::new(),
+            created_at: date.and_hms_opt(12, 0, 0).unwrap(),
+            default_lists: Vec::new(),
+            items: vec![
+                item("Tomate", Some("500 g"), Some("Gemüse"), true),
+                item("Gurke", None, Some("Gemüse"), false),
+                item("Milch, frisch", Some("1 l"), Some("Milch & Käse"), true),
+            ],
+            pushed_at: None,
+            pushed_to: None,
+            round_packages: false,
+        }
+    }
+
+    #[test]
+    fn formats_are_picked_from_the_accept_header() {
+        assert_eq!(
+            Format::from_accept("text/html, text/csv;q=0.9"),
+            Some(Format::Csv)
+        );
+        assert_eq!(Format::from_accept("application/json"), Some(Format::Json));
+        assert_eq!(Format::from_accept("*/*"), None);
+    }
+
+    #[test]
+    fn text_lists_checked_items_by_section() {
+        assert_eq!(
+            render(&list(), Format::Text),
+            "Einkaufsliste 03.03.2025 – 09.03.2025\n\
+             \n\
+             Gemüse\n\
+             - Tomate (500 g)\n\
+             \n\
+             Milch & Käse\n\
+             - Milch, frisch (1 l)\n"
+        );
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(
+            render(&list(), Format::Csv),
+            "Name,Menge,Abteilung\n\
+             Tomate,500 g,Gemüse\n\
+             \"Milch, frisch\",1 l,Milch & Käse\n"
+        );
+        assert_eq!(csv_field("5\" Pizza"), "\"5\"\" Pizza\"");
+    }
+}
@@ -1,10 +1,11 @@
 use crate::utils::Amount;
 use askama::Template;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-/// This represents a food card in the overview. For editing a food look at `EditFood`.
-#[derive(Debug, Serialize, Deserialize, Clone, Template)]
-#[template(path = "food/food.html")]
+/// This represents a food like it is stored in the database.
+/// For displaying it in the overview look at `FoodCard`, for editing a food look at `EditFood`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Food {
     pub id: Option<i32>,
     pub name: String,
@@ -14,6 +15,27 @@ pub struct Food {
     pub ingredients: Vec<Ingredient>,
 }
 
+/// Statistics of a food which are derived from the planned days in the calendar.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FoodStats {
+    /// The last day up to today where the food was planned.
+    pub last_cooked: Option<NaiveDate>,
+    /// How often the food was planned within the requested time span up to today.
+    pub times_cooked: usize,
+    /// The next day after today where the food is planned.
+    pub next_planned: Option<NaiveDate>,
+}
+
+/// This represents a food card in the overview.
+#[derive(Debug, Clone, Template)]
+#[template(path = "food/food.html")]
+pub struct FoodCard {
+    pub food: Food,
+    pub stats: FoodStats,
+    /// The number of months `stats.times_cooked` refers to.
+    pub months: u32,
+}
+
 /// This is an Ingredient like it is stored in the database.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Ingredient {
@@ -45,13 +67,13 @@ impl From<Ingredient> for EditIngredient {
     }
 }
 
-impl Into<Ingredient> for EditIngredient {
-    fn into(self) -> Ingredient {
+impl From<EditIngredient> for Ingredient {
+    fn from(ingredient: EditIngredient) -> Self {
         Ingredient {
-            id: self.id,
-            name: self.name,
-            amount: self.amount,
-            optional: self.optional,
+            id: ingredient.id,
+            name: ingredient.name,
+            amount: ingredient.amount,
+            optional: ingredient.optional,
         }
     }
 }
@@ -87,7 +109,7 @@ impl EditFood {
         }
     }
 
-    pub fn edit_food(food: Food, existing_items: Vec<String>, existing_tags: Vec<String>) -> Self {
+    pub fn edit(food: Food, existing_items: Vec<String>, existing_tags: Vec<String>) -> Self {
         EditFood {
             id: food.id,
             name: food.name,
@@ -101,15 +123,15 @@ impl EditFood {
     }
 }
 
-impl Into<Food> for EditFood {
-    fn into(self) -> Food {
+impl From<EditFood> for Food {
+    fn from(edit_food: EditFood) -> Self {
         Food {
-            id: self.id,
-            name: self.name,
-            tags: self.tags,
-            details: self.details,
-            portions: self.portions,
-            ingredients: self
+            id: edit_food.id,
+            name: edit_food.name,
+            tags: edit_food.tags,
+            details: edit_food.details,
+            portions: edit_food.portions,
+            ingredients: edit_food
                 .edit_ingredients
                 .into_iter()
                 .map(|i| i.into())
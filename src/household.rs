@@ -0,0 +1,66 @@
+use crate::food::Food;
+use serde::{Deserialize, Serialize};
+
+/// A member of the household with dietary restrictions.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Member {
+    pub id: Option<i32>,
+    pub name: String,
+    /// The member does not eat foods with any of these tags, e.g. "Fleisch".
+    pub forbidden_tags: Vec<String>,
+    /// The member does not eat foods with an ingredient containing any of these names, e.g. "nuss".
+    pub forbidden_ingredients: Vec<String>,
+    /// The names of the slots the member eats. If empty, the member eats every slot.
+    pub slots: Vec<String>,
+}
+
+impl Member {
+    pub fn eats(&self, slot: &str) -> bool {
+        self.slots.is_empty() || self.slots.iter().any(|s| s == slot)
+    }
+
+    /// Returns the forbidden tags and ingredients the food contains.
+    /// Both are compared case insensitive, ingredients also match parts of a name.
+    pub fn conflicts(&self, food: &Food) -> Vec<String> {
+        let mut conflicts: Vec<String> = self
+            .forbidden_tags
+            .iter()
+            .filter(|forbidden| food.tags.iter().any(|t| t.eq_ignore_ascii_case(forbidden)))
+            .cloned()
+            .collect();
+
+        for forbidden in &self.forbidden_ingredients {
+            let forbidden = forbidden.to_lowercase();
+            conflicts.extend(
+                food.ingredients
+                    .iter()
+                    .filter(|i| i.name.to_lowercase().contains(&forbidden))
+                    .map(|i| i.name.clone()),
+            );
+        }
+
+        conflicts
+    }
+}
+
+/// Returns a warning for every member eating the slot who must not eat the food.
+/// Example: "Anna: Fleisch, Haselnüsse"
+pub fn warnings(members: &[Member], slot: &str, food: &Food) -> Vec<String> {
+    members
+        .iter()
+        .filter(|m| m.eats(slot))
+        .filter_map(|m| {
+            let conflicts = m.conflicts(food);
+            if conflicts.is_empty() {
+                None
+            } else {
+                Some(format!("{}: {}", m.name, conflicts.join(", ")))
+            }
+        })
+        .collect()
+}
+
+/// Checks whether every member is allowed to eat the food.
+pub fn suitable_for_everyone(members: &[Member], food: &Food) -> bool {
+    members.iter().all(|m| m.conflicts(food).is_empty())
+}
@@ -0,0 +1,247 @@
+use crate::calendar::{Day, MealSlot, slot_label};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Configuration of the iCalendar feed.
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    /// The secret that has to be passed as `token` query parameter to access the feed.
+    /// If it is empty, the feed cannot be accessed at all.
+    pub token: String,
+    /// The number of days before today that are included in the feed.
+    #[serde(default = "default_past_days")]
+    pub past_days: u64,
+    /// The number of days after today that are included in the feed.
+    #[serde(default = "default_future_days")]
+    pub future_days: u64,
+    /// The start time of the events per slot name. Slots without a time become all-day events.
+    #[serde(default)]
+    pub times: HashMap<String, NaiveTime>,
+    /// The duration of the events which have a start time.
+    #[serde(default = "default_duration_minutes")]
+    pub duration_minutes: i64,
+}
+
+fn default_past_days() -> u64 {
+    30
+}
+
+fn default_future_days() -> u64 {
+    60
+}
+
+fn default_duration_minutes() -> i64 {
+    60
+}
+
+impl Config {
+    pub fn new(token: String) -> Self {
+        Config {
+            token,
+            past_days: default_past_days(),
+            future_days: default_future_days(),
+            times: HashMap::new(),
+            duration_minutes: default_duration_minutes(),
+        }
+    }
+
+    /// Whether the token grants access to the feed. An empty configured token grants no access.
+    /// The token is compared in constant time, so it cannot be guessed from the response times.
+    pub fn accepts(&self, token: &str) -> bool {
+        if self.token.is_empty() || token.len() != self.token.len() {
+            return false;
+        }
+
+        token
+            .bytes()
+            .zip(self.token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+    }
+}
+
+/// Renders the planned foods of the given days as an RFC 5545 calendar.
+/// Every planned food becomes a separate event with its scaled ingredients as description.
+pub fn render_feed(config: &Config, slots: &[MealSlot], days: &[Day]) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//rssensplaner//Essensplaner//DE".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:Essensplaner".to_string(),
+    ];
+
+    for day in days {
+        for meal in &day.meals {
+            let label = slot_label(slots, &meal.slot);
+
+            for (position, planned) in meal.foods.iter().enumerate() {
+                let food = &planned.food;
+                let food_id = food.id.unwrap_or_default();
+
+                let mut description = Vec::new();
+                match &planned.leftovers_of {
+                    Some(source) => description.push(format!(
+                        "Reste vom {} ({})",
+                        source.date.format("%d.%m.%Y"),
+                        slot_label(slots, &source.slot)
+                    )),
+                    None => {
+                        for ingredient in &food.ingredients {
+                            let line = match &ingredient.amount {
+                                Some((amount, unit)) => {
+                                    format!(
+                                        "- {} {} {}",
+                                        amount * planned.factor,
+                                        unit,
+                                        ingredient.name
+                                    )
+                                }
+                                None => format!("- {}", ingredient.name),
+                            };
+                            description.push(line);
+                        }
+                    }
+                }
+
+                let summary = match planned.leftovers_of {
+                    Some(_) => format!("{}: {} (Reste)", label, food.name),
+                    None => format!("{}: {}", label, food.name),
+                };
+
+                // The position keeps the UID unique if a food is planned twice in a meal
+                lines.push("BEGIN:VEVENT".to_string());
+                lines.push(format!(
+                    "UID:{}-{}-{}-{}@rssensplaner",
+                    day.date.format("%Y%m%d"),
+                    meal.slot,
+                    position,
+                    food_id
+                ));
+                lines.push(format!("DTSTAMP:{}", stamp));
+                lines.extend(event_times(config, &meal.slot, day.date));
+                lines.push(format!("SUMMARY:{}", escape_text(&summary)));
+                lines.push(format!(
+                    "DESCRIPTION:{}",
+                    escape_text(&description.join("\n"))
+                ));
+                lines.push("END:VEVENT".to_string());
+            }
+        }
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut feed = String::new();
+    for line in lines {
+        feed.push_str(&fold_line(&line));
+        feed.push_str("\r\n");
+    }
+    feed
+}
+
+/// Returns the DTSTART and DTEND properties of an event.
+/// The times are floating, so they are shown in the local time of the subscriber.
+fn event_times(config: &Config, slot: &str, date: NaiveDate) -> Vec<String> {
+    match config.times.get(slot) {
+        Some(time) => {
+            let start = NaiveDateTime::new(date, *time);
+            let end = start + TimeDelta::minutes(config.duration_minutes);
+            vec![
+                format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")),
+                format!("DTEND:{}", end.format("%Y%m%dT%H%M%S")),
+            ]
+        }
+        None => vec![
+            format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")),
+            format!(
+                "DTEND;VALUE=DATE:{}",
+                date.succ_opt().unwrap().format("%Y%m%d")
+            ),
+        ],
+    }
+}
+
+/// Escapes a value of the TEXT type.
+fn escape_text(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line, so no line is longer than 75 octets.
+/// The continuation lines start with a single space.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::{Meal, PlannedFood, default_slots};
+    use crate::food::Food;
+
+    #[test]
+    fn only_the_configured_token_is_accepted() {
+        let config = Config::new("secret".to_string());
+
+        assert!(config.accepts("secret"));
+        assert!(!config.accepts("secreT"));
+        assert!(!config.accepts("secret2"));
+        assert!(!config.accepts(""));
+    }
+
+    #[test]
+    fn empty_token_accepts_nothing() {
+        assert!(!Config::new(String::new()).accepts(""));
+    }
+
+    #[test]
+    fn uids_are_unique_for_a_food_planned_twice() {
+        let planned = PlannedFood {
+            food: Food {
+                id: Some(1),
+                name: "Salat".to_string(),
+                tags: Vec::new(),
+                details: String::new(),
+                portions: 4,
+                ingredients: Vec::new(),
+            },
+            factor: 1.0,
+            leftovers_of: None,
+            from_rule: None,
+        };
+        let day = Day {
+            id: None,
+            date: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+            meals: vec![Meal {
+                slot: "lunch".to_string(),
+                foods: vec![planned.clone(), planned],
+                headcount: None,
+            }],
+            note: String::new(),
+        };
+
+        let feed = render_feed(&Config::new("secret".to_string()), &default_slots(), &[day]);
+        let uids: Vec<&str> = feed.lines().filter(|l| l.starts_with("UID:")).collect();
+
+        assert_eq!(uids.len(), 2);
+        assert_ne!(uids[0], uids[1]);
+    }
+}
@@ -3,11 +3,19 @@ use anyhow::Result;
 pub mod bring;
 pub mod calendar;
 pub mod db;
+pub mod export;
 pub mod food;
+pub mod household;
+pub mod ical;
+pub mod names;
+pub mod pdf;
+pub mod planner;
+pub mod recurring;
 pub mod server;
 pub mod server_config;
 pub mod shopping;
 pub mod utils;
+pub mod week_template;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Maps a variant of an ingredient name to the name it is listed under,
+/// e.g. "Frühlingszwiebel" to "Lauchzwiebel".
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Alias {
+    pub alias: String,
+    pub name: String,
+}
+
+/// Decides which item names mean the same ingredient when the shopping list is generated.
+/// Names are compared case-insensitively with simple German plural forms folded,
+/// and aliases are resolved to the name they stand for.
+#[derive(Debug, Default)]
+pub struct Names {
+    /// The names the aliases stand for by their normalized alias.
+    aliases: HashMap<String, String>,
+}
+
+impl Names {
+    pub fn new(aliases: Vec<Alias>) -> Self {
+        Names {
+            aliases: aliases
+                .into_iter()
+                .map(|alias| (normalize(&alias.alias), alias.name))
+                .collect(),
+        }
+    }
+
+    /// Returns the key items are merged by and the name of the alias, if the name is one.
+    pub fn resolve(&self, name: &str) -> (String, Option<&str>) {
+        let key = normalize(name);
+        match self.aliases.get(&key) {
+            Some(target) => (normalize(target), Some(target.as_str())),
+            None => (key, None),
+        }
+    }
+}
+
+/// Plurals that are not formed by one of the common suffixes.
+const IRREGULAR_PLURALS: [(&str, &str); 1] = [("eier", "ei")];
+
+/// The suffixes of plural forms with the minimum length of the stem that remains.
+/// The stems are long enough, so words like "Eis" or "Mais" are not mistaken for plurals.
+const PLURAL_SUFFIXES: [(&str, usize); 5] = [("en", 3), ("er", 3), ("n", 3), ("e", 3), ("s", 4)];
+
+/// Lowercases the name and folds the plural of the last word, so "Tomaten" and "tomate"
+/// end up the same. This removes the common suffixes and only knows a few exceptions.
+pub fn normalize(name: &str) -> String {
+    let words: Vec<String> = name.split_whitespace().map(str::to_lowercase).collect();
+    let Some((last, rest)) = words.split_last() else {
+        return String::new();
+    };
+
+    let stem = IRREGULAR_PLURALS
+        .iter()
+        .find(|(plural, _)| plural == last)
+        .map(|(_, singular)| *singular)
+        .or_else(|| {
+            PLURAL_SUFFIXES.iter().find_map(|(suffix, min_stem)| {
+                last.strip_suffix(suffix)
+                    .filter(|stem| stem.chars().count() >= *min_stem)
+            })
+        })
+        .unwrap_or(last);
+
+    let mut normalized = rest.join(" ");
+    if !normalized.is_empty() {
+        normalized.push(' ');
+    }
+    normalized.push_str(stem);
+    normalized
+}
+
+/// Whether two names that are not merged might still mean the same ingredient.
+/// This is the case for small typos and for compounds with the same ending,
+/// like "Frühlingszwiebel" and "Lauchzwiebel".
+pub fn similar(a: &str, b: &str) -> bool {
+    let a: Vec<char> = normalize(a).chars().collect();
+    let b: Vec<char> = normalize(b).chars().collect();
+    if a == b {
+        return false;
+    }
+
+    let shorter = a.len().min(b.len());
+    let common_suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    if common_suffix >= 5 && common_suffix * 2 >= shorter {
+        return true;
+    }
+
+    let max_distance = if shorter >= 8 { 2 } else { 1 };
+    shorter >= 4 && distance(&a, &b) <= max_distance
+}
+
+/// The Levenshtein distance of the two words.
+fn distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, x) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, y) in b.iter().enumerate() {
+            let cost = if x == y { 0 } else { 1 };
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plurals_are_folded() {
+        assert_eq!(normalize("Tomaten"), normalize("tomate"));
+        assert_eq!(normalize("Zwiebeln"), normalize("Zwiebel"));
+        assert_eq!(normalize("Avocados"), normalize("Avocado"));
+        assert_eq!(normalize("Eier"), normalize("Ei"));
+    }
+
+    #[test]
+    fn only_the_last_word_is_folded() {
+        assert_eq!(normalize("  Rote   Zwiebeln "), "rote zwiebel");
+        assert_eq!(normalize(""), "");
+    }
+
+    #[test]
+    fn short_words_are_not_mistaken_for_plurals() {
+        assert_eq!(normalize("Eis"), "eis");
+        assert_ne!(normalize("Eis"), normalize("Ei"));
+        assert_eq!(normalize("Mais"), "mais");
+        assert_eq!(normalize("Reis"), "reis");
+        assert_eq!(normalize("Tee"), "tee");
+    }
+
+    #[test]
+    fn aliases_resolve_to_their_name() {
+        let names = Names::new(vec![Alias {
+            alias: "Frühlingszwiebeln".to_string(),
+            name: "Lauchzwiebel".to_string(),
+        }]);
+
+        assert_eq!(
+            names.resolve("Frühlingszwiebel"),
+            (normalize("Lauchzwiebel"), Some("Lauchzwiebel"))
+        );
+        assert_eq!(names.resolve("Tomaten"), (normalize("Tomate"), None));
+    }
+
+    #[test]
+    fn compounds_with_the_same_ending_are_similar() {
+        assert!(similar("Frühlingszwiebel", "Lauchzwiebel"));
+    }
+
+    #[test]
+    fn typos_are_similar() {
+        assert!(similar("Paprika", "Paprikq"));
+        assert!(similar("Mozzarella", "Mozarela"));
+    }
+
+    #[test]
+    fn different_or_equal_names_are_not_similar() {
+        assert!(!similar("Tomaten", "Tomate"));
+        assert!(!similar("Reis", "Eis"));
+        assert!(!similar("Salz", "Zucker"));
+        assert!(!similar("Ei", "Eis"));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::calendar::{Day, MealSlot, mult_portions, slot_label, weekday_name};
+use crate::shopping::FinalItem;
+use chrono::NaiveDate;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+/// A4 in points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+const ITALIC: Name = Name(b"F3");
+
+/// Renders the planned meals of the given days and optionally the shopping list on a new page.
+pub fn render_plan(
+    slots: &[MealSlot],
+    days: &[Day],
+    from: NaiveDate,
+    to: NaiveDate,
+    shopping: Option<(&[FinalItem], &[FinalItem])>,
+) -> Vec<u8> {
+    let title = format!(
+        "Essensplan {} – {}",
+        from.format("%d.%m.%Y"),
+        to.format("%d.%m.%Y")
+    );
+
+    let mut document = Document::new();
+    document.line(BOLD, 18.0, &title, 0.0);
+    document.space(8.0);
+
+    for day in days {
+        document.keep_together(14.0 + 14.0 * day.meals.len() as f32);
+        document.line(
+            BOLD,
+            12.0,
+            &format!("{}, {}", weekday_name(&day.date), day.date.format("%d.%m.")),
+            0.0,
+        );
+        if !day.note.is_empty() {
+            document.wrapped(ITALIC, 10.0, &day.note, 10.0);
+        }
+
+        for meal in &day.meals {
+            let foods: Vec<String> = meal
+                .foods
+                .iter()
+                .map(|planned| {
+                    let portions = mult_portions(&planned.food.portions, &planned.factor);
+                    if planned.is_leftovers() {
+                        format!("{} (Reste)", planned.food.name)
+                    } else {
+                        format!("{} ({} Portionen)", planned.food.name, portions)
+                    }
+                })
+                .collect();
+            let foods = if foods.is_empty() {
+                "-".to_string()
+            } else {
+                foods.join(", ")
+            };
+
+            document.wrapped(
+                REGULAR,
+                11.0,
+                &format!("{}: {}", slot_label(slots, &meal.slot), foods),
+                10.0,
+            );
+        }
+        document.space(6.0);
+    }
+
+    if let Some((items, optional_items)) = shopping {
+        document.new_page();
+        document.line(BOLD, 18.0, "Einkaufsliste", 0.0);
+        document.space(8.0);
+        shopping_lines(&mut document, items);
+
+        if !optional_items.is_empty() {
+            document.space(8.0);
+            document.line(BOLD, 12.0, "Optional", 0.0);
+            shopping_lines(&mut document, optional_items);
+        }
+    }
+
+    document.finish(&title)
+}
+
+/// Adds a checkbox line for every item.
+fn shopping_lines(document: &mut Document, items: &[FinalItem]) {
+    for item in items {
+        let text = match &item.amount {
+            Some(amount) => format!("{} ({})", item.name, amount),
+            None => item.name.clone(),
+        };
+        document.checkbox(11.0);
+        document.wrapped(REGULAR, 11.0, &text, 16.0);
+    }
+}
+
+/// Lays out text from top to bottom and starts a new page when the current one is full.
+/// The standard Helvetica fonts are used, so no font has to be embedded.
+struct Document {
+    pages: Vec<Content>,
+    y: f32,
+}
+
+impl Document {
+    fn new() -> Self {
+        Document {
+            pages: vec![Content::new()],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn content(&mut self) -> &mut Content {
+        self.pages.last_mut().unwrap()
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Content::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Starts a new page if less than `height` is left on the current one.
+    fn keep_together(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn space(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    /// Writes a single line of text without wrapping it.
+    fn line(&mut self, font: Name, size: f32, text: &str, indent: f32) {
+        let leading = size * 1.3;
+        self.keep_together(leading);
+        self.y -= leading;
+
+        let y = self.y;
+        let encoded = encode(text);
+        self.content()
+            .begin_text()
+            .set_font(font, size)
+            .next_line(MARGIN + indent, y)
+            .show(Str(&encoded))
+            .end_text();
+    }
+
+    /// Writes the text and wraps it at word boundaries to fit the page width.
+    fn wrapped(&mut self, font: Name, size: f32, text: &str, indent: f32) {
+        let max_width = PAGE_WIDTH - 2.0 * MARGIN - indent;
+        let mut current = String::new();
+
+        for word in text.split_whitespace() {
+            let candidate = if current.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", current, word)
+            };
+
+            if text_width(&candidate, size) > max_width && !current.is_empty() {
+                self.line(font, size, &current, indent);
+                current = word.to_string();
+            } else {
+                current = candidate;
+            }
+        }
+
+        if !current.is_empty() {
+            self.line(font, size, &current, indent);
+        }
+    }
+
+    /// Draws an empty checkbox in front of the next line.
+    fn checkbox(&mut self, size: f32) {
+        self.keep_together(size * 1.3);
+        let y = self.y - size * 1.3 - 1.0;
+        self.content()
+            .set_line_width(0.8)
+            .rect(MARGIN + 2.0, y, size * 0.8, size * 0.8)
+            .stroke();
+    }
+
+    fn finish(self, title: &str) -> Vec<u8> {
+        let mut pdf = Pdf::new();
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let info_id = Ref::new(3);
+        let fonts = [
+            (REGULAR, Ref::new(4), Name(b"Helvetica")),
+            (BOLD, Ref::new(5), Name(b"Helvetica-Bold")),
+            (ITALIC, Ref::new(6), Name(b"Helvetica-Oblique")),
+        ];
+        let first_page_id = 7;
+
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.document_info(info_id).title(TextStr(title));
+
+        let page_ids: Vec<Ref> = (0..self.pages.len() as i32)
+            .map(|i| Ref::new(first_page_id + 2 * i))
+            .collect();
+        pdf.pages(page_tree_id)
+            .kids(page_ids.iter().copied())
+            .count(page_ids.len() as i32);
+
+        for (_, font_id, base_font) in fonts {
+            pdf.type1_font(font_id)
+                .base_font(base_font)
+                .encoding_predefined(Name(b"WinAnsiEncoding"));
+        }
+
+        for (page_id, content) in page_ids.into_iter().zip(self.pages) {
+            let content_id = Ref::new(page_id.get() + 1);
+
+            let mut page = pdf.page(page_id);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+            page.parent(page_tree_id);
+            page.contents(content_id);
+            let mut resources = page.resources();
+            let mut font_dict = resources.fonts();
+            for (font_name, font_id, _) in fonts {
+                font_dict.pair(font_name, font_id);
+            }
+            font_dict.finish();
+            resources.finish();
+            page.finish();
+
+            pdf.stream(content_id, &content.finish());
+        }
+
+        pdf.finish()
+    }
+}
+
+/// Encodes the text for the WinAnsi encoding of the standard fonts.
+/// Latin-1 characters like umlauts map directly, unsupported characters become '?'.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            '€' => 0x80,
+            '–' => 0x96,
+            '—' => 0x97,
+            '„' => 0x84,
+            '“' => 0x93,
+            '”' => 0x94,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '•' => 0x95,
+            c if (c as u32) < 0x80 || (0xA0..=0xFF).contains(&(c as u32)) => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Estimates the width of the text in Helvetica, which is good enough for wrapping lines.
+fn text_width(text: &str, size: f32) -> f32 {
+    let em: f32 = text
+        .chars()
+        .map(|c| match c {
+            'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '\'' | '!' | '|' | 'I' => 0.28,
+            'f' | 't' | 'r' | ' ' | '(' | ')' | '-' => 0.33,
+            'm' | 'w' | 'M' | 'W' => 0.85,
+            c if c.is_uppercase() => 0.68,
+            _ => 0.56,
+        })
+        .sum();
+    em * size
+}
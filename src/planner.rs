@@ -0,0 +1,213 @@
+use crate::calendar::{Day, MealSlot, PlannedFood, slot_label};
+use crate::food::{Food, FoodStats};
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Configuration of the rules the planner follows when filling empty slots.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Minimum number of days before the same food is planned again.
+    pub min_repeat_days: u32,
+    /// Maximum number of times the same main ingredient is used within the planned range.
+    pub max_main_ingredient_uses: usize,
+    /// Tags that are required or excluded on specific weekdays.
+    pub weekdays: Vec<WeekdayRule>,
+    /// The names of the slots that get filled. If not set, all configured slots are filled.
+    pub slots: Option<Vec<String>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            min_repeat_days: 7,
+            max_main_ingredient_uses: 2,
+            weekdays: Vec::new(),
+            slots: None,
+        }
+    }
+}
+
+/// Restricts the foods that can be planned on a weekday by their tags.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeekdayRule {
+    pub weekday: Weekday,
+    /// A food has to have all of these tags.
+    #[serde(default)]
+    pub required_tags: Vec<String>,
+    /// A food must not have any of these tags.
+    #[serde(default)]
+    pub excluded_tags: Vec<String>,
+}
+
+/// The proposed plan for a single day.
+#[derive(Debug)]
+pub struct DayProposal {
+    pub id: Option<i32>,
+    pub date: NaiveDate,
+    pub meals: Vec<MealProposal>,
+}
+
+/// The proposal for a single slot of a day.
+/// Slots that already have planned foods keep them and get no suggestion.
+#[derive(Debug)]
+pub struct MealProposal {
+    pub slot: String,
+    pub label: String,
+    /// The portions a chosen food gets planned with.
+    pub portions: i32,
+    pub headcount: Option<i32>,
+    pub existing: Vec<PlannedFood>,
+    pub suggestion: Option<Food>,
+    /// Whether the planner is allowed to fill this slot.
+    pub fillable: bool,
+}
+
+impl MealProposal {
+    /// Whether the user can choose a food for this slot.
+    pub fn is_open(&self) -> bool {
+        self.existing.is_empty() && self.fillable
+    }
+}
+
+/// The main ingredient of a food is its first non optional ingredient.
+/// It is normalized to lowercase, so different spellings are counted together.
+pub fn main_ingredient(food: &Food) -> Option<String> {
+    food.ingredients
+        .iter()
+        .find(|i| !i.optional)
+        .map(|i| i.name.trim().to_lowercase())
+}
+
+/// Fills all empty slots of the days in `from..to` with suggestions.
+/// Only configured slots which are selected in the planner config are filled.
+///
+/// `days` should also contain the days around the range, so foods planned shortly before or after
+/// the range are respected for the repetition rule. Foods that were not cooked for the longest
+/// time according to `stats` are preferred.
+pub fn suggest(
+    config: &Config,
+    slots: &[MealSlot],
+    foods: &[Food],
+    stats: &HashMap<i32, FoodStats>,
+    days: Vec<Day>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<DayProposal> {
+    let in_range = |date: NaiveDate| date >= from && date < to;
+
+    // Dates on which each food is planned and how often each main ingredient is used.
+    let mut planned: HashMap<i32, Vec<NaiveDate>> = stats
+        .iter()
+        .filter_map(|(id, s)| s.last_cooked.map(|d| (*id, vec![d])))
+        .collect();
+    let mut main_uses: HashMap<String, usize> = HashMap::new();
+    for day in &days {
+        for planned_food in day.planned_foods() {
+            if let Some(id) = planned_food.food.id {
+                planned.entry(id).or_default().push(day.date);
+            }
+
+            // Leftovers do not use the main ingredient again
+            if planned_food.is_leftovers() || !in_range(day.date) {
+                continue;
+            }
+            if let Some(main) = main_ingredient(&planned_food.food) {
+                *main_uses.entry(main).or_default() += 1;
+            }
+        }
+    }
+
+    let mut proposals = Vec::new();
+
+    for day in days.into_iter().filter(|d| in_range(d.date)) {
+        let mut meals = Vec::new();
+        for meal in day.meals {
+            let slot = slots.iter().find(|s| s.name == meal.slot);
+            let fillable = slot.is_some()
+                && config
+                    .slots
+                    .as_ref()
+                    .is_none_or(|selected| selected.contains(&meal.slot));
+
+            let suggestion = if meal.foods.is_empty() && fillable {
+                pick_food(config, foods, day.date, &planned, &main_uses).cloned()
+            } else {
+                None
+            };
+
+            if let Some(food) = &suggestion {
+                planned.entry(food.id.unwrap()).or_default().push(day.date);
+                if let Some(main) = main_ingredient(food) {
+                    *main_uses.entry(main).or_default() += 1;
+                }
+            }
+
+            meals.push(MealProposal {
+                label: slot_label(slots, &meal.slot).to_string(),
+                portions: meal.headcount.or(slot.map(|s| s.portions)).unwrap_or(4),
+                headcount: meal.headcount,
+                slot: meal.slot,
+                existing: meal.foods,
+                suggestion,
+                fillable,
+            });
+        }
+
+        proposals.push(DayProposal {
+            id: day.id,
+            date: day.date,
+            meals,
+        });
+    }
+
+    proposals
+}
+
+/// Picks the food which fulfills all rules for the given date and was not planned for the
+/// longest time. Returns `None` if no food fulfills the rules.
+fn pick_food<'a>(
+    config: &Config,
+    foods: &'a [Food],
+    date: NaiveDate,
+    planned: &HashMap<i32, Vec<NaiveDate>>,
+    main_uses: &HashMap<String, usize>,
+) -> Option<&'a Food> {
+    let weekday_rules: Vec<&WeekdayRule> = config
+        .weekdays
+        .iter()
+        .filter(|r| r.weekday == date.weekday())
+        .collect();
+
+    let has_tag = |food: &Food, tag: &String| food.tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+
+    foods
+        .iter()
+        .filter(|food| food.id.is_some())
+        .filter(|food| {
+            weekday_rules.iter().all(|rule| {
+                rule.required_tags.iter().all(|t| has_tag(food, t))
+                    && !rule.excluded_tags.iter().any(|t| has_tag(food, t))
+            })
+        })
+        .filter(|food| {
+            let dates = planned.get(&food.id.unwrap());
+            !dates
+                .into_iter()
+                .flatten()
+                .any(|d| (*d - date).num_days().unsigned_abs() < u64::from(config.min_repeat_days))
+        })
+        .filter(|food| match main_ingredient(food) {
+            Some(main) => {
+                main_uses.get(&main).copied().unwrap_or(0) < config.max_main_ingredient_uses
+            }
+            None => true,
+        })
+        .min_by_key(|food| {
+            let last_planned = planned
+                .get(&food.id.unwrap())
+                .and_then(|dates| dates.iter().filter(|d| **d < date).max().copied());
+            (last_planned, food.id)
+        })
+}
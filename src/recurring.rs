@@ -0,0 +1,141 @@
+use crate::calendar::weekday_label;
+use crate::food::Food;
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// A rule that plans a food regularly, e.g. "every Friday: pizza".
+///
+/// Rules only generate meals for today and future days and only for slots which were not saved
+/// for that day yet. Saving a day turns the generated meals into regular ones, removing them
+/// overrides the rule for that day.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecurringRule {
+    pub id: Option<i32>,
+    /// The rule applies on this weekday. Either this or `interval_days` has to be set.
+    pub weekday: Option<Weekday>,
+    /// The rule applies every n days, counted from `start`.
+    pub interval_days: Option<i32>,
+    pub slot: String,
+    /// The food that gets planned. If not set, a food with `tag` is picked.
+    pub food_id: Option<i32>,
+    pub tag: Option<String>,
+    pub factor: f32,
+    pub start: NaiveDate,
+    /// The last day the rule applies on (inclusive).
+    pub end: Option<NaiveDate>,
+}
+
+impl RecurringRule {
+    /// Checks whether the rule generates a meal on the given date.
+    pub fn applies_on(&self, date: NaiveDate) -> bool {
+        if date < self.start || self.end.is_some_and(|end| date > end) {
+            return false;
+        }
+
+        match (self.weekday, self.interval_days) {
+            (Some(weekday), _) => date.weekday() == weekday,
+            (None, Some(interval)) if interval > 0 => {
+                (date - self.start).num_days() % i64::from(interval) == 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Picks the food for the given date.
+    ///
+    /// For rules with a tag a food with that tag is picked. The pick looks random but is stable
+    /// for a date, so the calendar does not change on every reload.
+    pub fn pick_food<'a>(&self, date: NaiveDate, foods: &'a [Food]) -> Option<&'a Food> {
+        if let Some(id) = self.food_id {
+            return foods.iter().find(|f| f.id == Some(id));
+        }
+
+        let tag = self.tag.as_ref()?;
+        let mut candidates: Vec<&Food> = foods
+            .iter()
+            .filter(|f| f.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+        candidates.sort_by_key(|f| f.id);
+
+        let seed = (date.num_days_from_ce() as u64) << 16 | self.id.unwrap_or_default() as u64;
+        let index = mix(seed) % candidates.len() as u64;
+        Some(candidates[index as usize])
+    }
+
+    /// Describes when the rule applies, e.g. "Jeden Freitag".
+    pub fn describe_recurrence(&self) -> String {
+        match (self.weekday, self.interval_days) {
+            (Some(weekday), _) => format!("Jeden {}", weekday_label(weekday)),
+            (None, Some(interval)) => format!("Alle {} Tage", interval),
+            _ => "Nie".to_string(),
+        }
+    }
+}
+
+/// Scrambles the bits of the seed (splitmix64), so neighbouring dates pick different foods.
+fn mix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    fn rule(weekday: Option<Weekday>, interval_days: Option<i32>) -> RecurringRule {
+        RecurringRule {
+            id: Some(1),
+            weekday,
+            interval_days,
+            slot: "dinner".to_string(),
+            food_id: Some(1),
+            tag: None,
+            factor: 1.0,
+            // A monday
+            start: date(3),
+            end: Some(date(20)),
+        }
+    }
+
+    #[test]
+    fn weekday_rules_apply_on_their_weekday() {
+        let rule = rule(Some(Weekday::Fri), None);
+
+        assert!(rule.applies_on(date(7)));
+        assert!(rule.applies_on(date(14)));
+        assert!(!rule.applies_on(date(8)));
+    }
+
+    #[test]
+    fn interval_rules_count_from_the_start() {
+        let rule = rule(None, Some(3));
+
+        assert!(rule.applies_on(date(3)));
+        assert!(rule.applies_on(date(6)));
+        assert!(!rule.applies_on(date(7)));
+    }
+
+    #[test]
+    fn rules_only_apply_within_their_range() {
+        let rule = rule(Some(Weekday::Mon), None);
+
+        assert!(!rule.applies_on(date(3) - chrono::Days::new(7)));
+        assert!(rule.applies_on(date(17)));
+        assert!(!rule.applies_on(date(24)));
+    }
+
+    #[test]
+    fn rules_without_recurrence_never_apply() {
+        assert!(!rule(None, None).applies_on(date(3)));
+        assert!(!rule(None, Some(0)).applies_on(date(3)));
+    }
+}
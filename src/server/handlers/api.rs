@@ -0,0 +1,108 @@
+use super::AppError;
+use crate::{
+    calendar::{ResponseDay, ResponseMeal},
+    db::{self},
+    server::AppState,
+};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::{Days, NaiveDate};
+use serde::Deserialize;
+
+/// The maximum number of days that can be requested at once.
+const MAX_DAYS: i64 = 366;
+
+#[derive(Deserialize)]
+pub struct DaysParams {
+    from: NaiveDate,
+    /// The last day that is returned (inclusive). Defaults to `from`.
+    to: Option<NaiveDate>,
+}
+
+/// The meals and the note of a day which is addressed by its date.
+#[derive(Debug, Deserialize)]
+pub struct DayRequest {
+    pub meals: Vec<ResponseMeal>,
+    /// If not set, the stored note is kept.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Returns all days from `from` until `to` with their planned foods as JSON.
+/// Days without planned foods are included as well.
+pub async fn get_days_handler(
+    State(state): State<AppState>,
+    Query(params): Query<DaysParams>,
+) -> Result<Response, AppError> {
+    let to = params.to.unwrap_or(params.from);
+    let count = (to - params.from).num_days() + 1;
+    if !(1..=MAX_DAYS).contains(&count) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            format!("The range has to contain between 1 and {} days", MAX_DAYS),
+        )
+            .into_response());
+    }
+
+    let conn = state.pool.get().await?;
+    let days = db::calendar::get_days(
+        conn,
+        params.from,
+        params
+            .from
+            .checked_add_days(Days::new(count as u64))
+            .unwrap(),
+        &state.slots,
+    )
+    .await?;
+
+    Ok(Json(days).into_response())
+}
+
+/// Returns a single day with its planned foods as JSON.
+pub async fn get_day_handler(
+    State(state): State<AppState>,
+    Path(date): Path<NaiveDate>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let days = db::calendar::get_days(conn, date, date.succ_opt().unwrap(), &state.slots).await?;
+
+    Ok(Json(days.into_iter().next()))
+}
+
+/// Replaces the meals of a day and returns the stored day.
+pub async fn put_day_handler(
+    State(state): State<AppState>,
+    Path(date): Path<NaiveDate>,
+    Json(request): Json<DayRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let day = ResponseDay {
+        id: None,
+        date,
+        meals: request.meals,
+        note: request.note,
+    };
+
+    let conn = state.pool.get().await?;
+    db::calendar::update_day(conn, day).await?;
+
+    let conn = state.pool.get().await?;
+    let days = db::calendar::get_days(conn, date, date.succ_opt().unwrap(), &state.slots).await?;
+
+    Ok(Json(days.into_iter().next()))
+}
+
+/// Removes all meals and the note of a day.
+pub async fn delete_day_handler(
+    State(state): State<AppState>,
+    Path(date): Path<NaiveDate>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::calendar::delete_day(conn, date).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,6 +1,17 @@
 use super::AppError;
-use crate::{server::AppState, shopping::AddItemsRequest};
-use axum::{Json, extract::State, response::IntoResponse};
+use crate::{
+    db::{self},
+    server::AppState,
+    shopping::{AddItemsRequest, PushListRequest},
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+const NOT_CONFIGURED: &str = "Bring! is not configured";
 
 /// The submitted items are added to the specified list.
 ///
@@ -9,8 +20,10 @@ use axum::{Json, extract::State, response::IntoResponse};
 pub async fn add_bring_handler(
     State(state): State<AppState>,
     Json(request): Json<AddItemsRequest>,
-) -> Result<impl IntoResponse, AppError> {
-    let bring = state.bring.clone();
+) -> Result<Response, AppError> {
+    let Some(bring) = state.bring.clone() else {
+        return Ok((StatusCode::SERVICE_UNAVAILABLE, NOT_CONFIGURED).into_response());
+    };
 
     for item in request.items.iter() {
         let amount = match &item.amount {
@@ -23,7 +36,66 @@ pub async fn add_bring_handler(
             .await?;
     }
 
-    Ok(Json(
-        serde_json::json!({ "result": "success", "item_count": request.items.len() }),
-    ))
+    Ok(
+        Json(serde_json::json!({ "result": "success", "item_count": request.items.len() }))
+            .into_response(),
+    )
+}
+
+/// The checked items of a stored shopping list are added to the specified list.
+/// The push is recorded with the shopping list, so it shows up in the history,
+/// and with the pushed default items, so their interval starts again.
+///
+/// This is not a transactional operation. If one of the items cannot be added, the already
+/// added items stay in the Bring list, but the push is not recorded. The error response lists
+/// the added items, because pushing again adds them a second time.
+pub async fn push_list_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(request): Json<PushListRequest>,
+) -> Result<Response, AppError> {
+    let Some(bring) = state.bring.clone() else {
+        return Ok((StatusCode::SERVICE_UNAVAILABLE, NOT_CONFIGURED).into_response());
+    };
+
+    let conn = state.pool.get().await?;
+    let list = db::shopping::get_list(conn, id).await?;
+
+    let items: Vec<_> = list.items.iter().filter(|i| i.checked).collect();
+    for (index, item) in items.iter().enumerate() {
+        let saved = bring
+            .save_item(
+                request.list_id.clone(),
+                item.name.clone(),
+                item.amount.clone().unwrap_or_default(),
+            )
+            .await;
+
+        if let Err(err) = saved {
+            let added: Vec<&str> = items[..index].iter().map(|i| i.name.as_str()).collect();
+            return Ok((
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({
+                    "result": "error",
+                    "error": err.to_string(),
+                    "added": added,
+                    "item_count": items.len(),
+                })),
+            )
+                .into_response());
+        }
+    }
+
+    let conn = state.pool.get().await?;
+    let now = chrono::Local::now().naive_local();
+    db::shopping::set_list_pushed(conn, id, now, &request.list_name).await?;
+
+    let default_ids: Vec<i32> = items
+        .iter()
+        .flat_map(|item| item.default_ids.iter().copied())
+        .collect();
+    let conn = state.pool.get().await?;
+    db::shopping::set_last_added(conn, &default_ids, now.date()).await?;
+
+    Ok(Json(serde_json::json!({ "result": "success", "item_count": items.len() })).into_response())
 }
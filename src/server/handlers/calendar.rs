@@ -1,51 +1,301 @@
 use super::AppError;
 use crate::{
-    calendar::{Day, ResponseDay},
-    db,
+    calendar::{
+        ClearRangeRequest, CopyWeekRequest, Day, DayPairRequest, DayView, MealSlot,
+        MoveMealRequest, ResponseDay, slot_label,
+    },
+    db, ical,
+    names::Names,
+    pdf,
     server::AppState,
+    shopping::{FinalItem, ListItem, OptionalIngredients, collect_items, compress_items},
 };
 use askama::Template;
 use axum::{
     Json,
-    extract::State,
-    response::{Html, IntoResponse},
+    extract::{Path, Query, State},
+    http::{
+        StatusCode,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    },
+    response::{Html, IntoResponse, Response},
 };
-use chrono::{Days, NaiveDate};
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct CalendarParams {
+    /// Any day of the first week that is shown. Defaults to the last week.
+    start: Option<NaiveDate>,
+    /// The number of weeks that are shown.
+    weeks: Option<u64>,
+    /// The id of a food that is being placed in the calendar.
+    placing: Option<i32>,
+}
 
 /// This handler returns the html for the calendar.
 pub async fn get_calendar_handler(
     State(state): State<AppState>,
+    Query(params): Query<CalendarParams>,
 ) -> Result<impl IntoResponse, AppError> {
     let today = chrono::Local::now().date_naive();
 
-    let current_week = today.week(chrono::Weekday::Mon);
-    let first = current_week.first_day();
-    let last = current_week.last_day();
-    let from = first.checked_sub_days(Days::new(7)).unwrap();
-    let to = last.checked_add_days(Days::new(8)).unwrap();
+    let start = params
+        .start
+        .unwrap_or_else(|| today.checked_sub_days(Days::new(7)).unwrap());
+    let weeks = params.weeks.unwrap_or(3).clamp(1, 12);
+
+    let from = start.week(Weekday::Mon).first_day();
+    let to = from.checked_add_days(Days::new(7 * weeks)).unwrap();
 
     let conn = state.pool.get().await?;
-    let days = db::calendar::get_days(conn, from, to).await?;
+    let days = db::calendar::get_days(conn, from, to, &state.slots).await?;
+    let conn = state.pool.get().await?;
+    let members = db::household::get_members(conn).await?;
+    let days: Vec<DayView> = days
+        .iter()
+        .map(|day| DayView {
+            day,
+            slots: &state.slots,
+            members: &members,
+        })
+        .collect();
 
     #[derive(Debug, Template)]
     #[template(path = "calendar/index.html")]
     struct Calendar<'a> {
-        last_week: &'a [Day],
-        current_week: &'a [Day],
-        next_week: &'a [Day],
+        weeks: Vec<&'a [DayView<'a>]>,
+        slots: &'a [MealSlot],
+        week_count: u64,
+        from: NaiveDate,
+        previous: NaiveDate,
+        next: NaiveDate,
         current_date: NaiveDate,
+        placing: Option<i32>,
     }
 
     let calendar = Calendar {
-        last_week: &days[0..7],
-        current_week: &days[7..14],
-        next_week: &days[14..21],
+        weeks: days.chunks(7).collect(),
+        slots: &state.slots,
+        week_count: weeks,
+        from,
+        previous: from.checked_sub_days(Days::new(7)).unwrap(),
+        next: from.checked_add_days(Days::new(7)).unwrap(),
         current_date: today,
+        placing: params.placing,
     };
 
     Ok(Html(calendar.render()?))
 }
 
+#[derive(Deserialize)]
+pub struct MonthParams {
+    /// The month formatted as `YYYY-MM`. Defaults to the current month.
+    month: Option<String>,
+}
+
+/// This handler returns the html for a compact overview of a whole month.
+pub async fn get_month_handler(
+    State(state): State<AppState>,
+    Query(params): Query<MonthParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let today = chrono::Local::now().date_naive();
+
+    let first_of_month = match params.month {
+        Some(month) => NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d")?,
+        None => today.with_day(1).unwrap(),
+    };
+    let next_month = first_of_month.checked_add_months(Months::new(1)).unwrap();
+    let previous_month = first_of_month.checked_sub_months(Months::new(1)).unwrap();
+
+    // Show complete weeks
+    let from = first_of_month.week(Weekday::Mon).first_day();
+    let to = next_month
+        .pred_opt()
+        .unwrap()
+        .week(Weekday::Mon)
+        .last_day()
+        .succ_opt()
+        .unwrap();
+
+    let conn = state.pool.get().await?;
+    let days = db::calendar::get_days(conn, from, to, &state.slots).await?;
+
+    #[derive(Debug, Template)]
+    #[template(path = "calendar/month.html")]
+    struct Month<'a> {
+        weeks: Vec<&'a [Day]>,
+        slots: &'a [MealSlot],
+        month: NaiveDate,
+        previous: NaiveDate,
+        next: NaiveDate,
+        current_date: NaiveDate,
+    }
+
+    impl Month<'_> {
+        fn label<'b>(&'b self, slot: &'b str) -> &'b str {
+            slot_label(self.slots, slot)
+        }
+    }
+
+    let month = Month {
+        weeks: days.chunks(7).collect(),
+        slots: &state.slots,
+        month: first_of_month,
+        previous: previous_month,
+        next: next_month,
+        current_date: today,
+    };
+
+    Ok(Html(month.render()?))
+}
+
+#[derive(Deserialize)]
+pub struct IcalParams {
+    token: Option<String>,
+}
+
+/// This handler returns the planned foods as iCalendar feed.
+/// The feed can only be accessed with the configured token, so it can be subscribed to by
+/// calendar apps without a browser session.
+pub async fn get_ical_handler(
+    State(state): State<AppState>,
+    Query(params): Query<IcalParams>,
+) -> Result<Response, AppError> {
+    let Some(config) = state.ical.as_ref() else {
+        return Ok((StatusCode::NOT_FOUND, "iCalendar feed is not configured").into_response());
+    };
+    if !params
+        .token
+        .as_deref()
+        .is_some_and(|token| config.accepts(token))
+    {
+        return Ok((StatusCode::FORBIDDEN, "Invalid token").into_response());
+    }
+
+    let today = chrono::Local::now().date_naive();
+    let from = today.checked_sub_days(Days::new(config.past_days)).unwrap();
+    let to = today
+        .checked_add_days(Days::new(config.future_days + 1))
+        .unwrap();
+
+    let conn = state.pool.get().await?;
+    let days = db::calendar::get_days(conn, from, to, &state.slots).await?;
+
+    let feed = ical::render_feed(config, &state.slots, &days);
+
+    Ok(([(CONTENT_TYPE, "text/calendar; charset=utf-8")], feed).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct PdfParams {
+    /// The first day of the plan. Defaults to the monday of the current week.
+    from: Option<NaiveDate>,
+    /// The last day of the plan (inclusive). Defaults to six days after `from`.
+    to: Option<NaiveDate>,
+    /// Adds a page with the shopping list for the same days.
+    #[serde(default)]
+    shopping: bool,
+    /// The comma separated ids of the default lists whose items are added to the shopping list.
+    defaults: Option<String>,
+    optional: Option<OptionalIngredients>,
+    /// A stored shopping list which is printed instead of generating a new one.
+    /// Its checked items are listed first, the unchecked ones are listed as optional.
+    /// The range of the plan defaults to the range of the list.
+    list: Option<i32>,
+}
+
+/// Returns a printable PDF of the plan and optionally the shopping list.
+pub async fn get_pdf_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PdfParams>,
+) -> Result<Response, AppError> {
+    let stored = match params.list {
+        Some(id) => {
+            let conn = state.pool.get().await?;
+            Some(db::shopping::get_list(conn, id).await?)
+        }
+        None => None,
+    };
+
+    let today = chrono::Local::now().date_naive();
+    let from = params
+        .from
+        .or(stored.as_ref().map(|l| l.from))
+        .unwrap_or_else(|| today.week(Weekday::Mon).first_day());
+    let to = params
+        .to
+        .or(stored.as_ref().map(|l| l.to))
+        .unwrap_or_else(|| from.checked_add_days(Days::new(6)).unwrap());
+    if to < from || (to - from).num_days() > 31 {
+        return Ok((StatusCode::BAD_REQUEST, "Invalid range").into_response());
+    }
+
+    let conn = state.pool.get().await?;
+    let days = db::calendar::get_days(conn, from, to.succ_opt().unwrap(), &state.slots).await?;
+
+    let shopping = match (params.shopping, stored) {
+        (false, _) => None,
+        (true, Some(list)) => {
+            let (checked, unchecked): (Vec<ListItem>, Vec<ListItem>) =
+                list.items.into_iter().partition(|i| i.checked);
+            Some((
+                checked.into_iter().map(FinalItem::from).collect(),
+                unchecked.into_iter().map(FinalItem::from).collect(),
+            ))
+        }
+        (true, None) => {
+            let mut items = Vec::new();
+            if let Some(defaults) = &params.defaults {
+                let list_ids = defaults
+                    .split(',')
+                    .filter(|id| !id.trim().is_empty())
+                    .map(|id| id.trim().parse())
+                    .collect::<Result<Vec<i32>, _>>()?;
+                let conn = state.pool.get().await?;
+                items.extend(db::shopping::get_items(conn, &list_ids).await?);
+            }
+            let conn = state.pool.get().await?;
+            let sources = db::calendar::get_leftover_sources(conn, &days, &state.slots).await?;
+            let optional_items = collect_items(
+                &days,
+                &sources,
+                params.optional.unwrap_or_default(),
+                &mut items,
+            );
+            let conn = state.pool.get().await?;
+            let names = Names::new(db::shopping::get_aliases(conn).await?);
+            Some((
+                compress_items(&items, &names),
+                compress_items(&optional_items, &names),
+            ))
+        }
+    };
+
+    let pdf = pdf::render_plan(
+        &state.slots,
+        &days,
+        from,
+        to,
+        shopping
+            .as_ref()
+            .map(|(items, optional)| (items.as_slice(), optional.as_slice())),
+    );
+
+    let disposition = format!(
+        "attachment; filename=\"essensplan-{}.pdf\"",
+        from.format("%Y-%m-%d")
+    );
+    Ok((
+        [
+            (CONTENT_TYPE, "application/pdf".to_string()),
+            (CONTENT_DISPOSITION, disposition),
+        ],
+        pdf,
+    )
+        .into_response())
+}
+
 /// This will update the day in the internal database.
 pub async fn update_day_handler(
     State(state): State<AppState>,
@@ -57,11 +307,85 @@ pub async fn update_day_handler(
     Ok(Json(serde_json::json!({ "result": "success" })))
 }
 
-/// This handler returns the day specified by the given ID.
-pub async fn get_day_handler(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+/// Copies the meals of a whole week to another week.
+pub async fn copy_week_handler(
+    State(state): State<AppState>,
+    Json(request): Json<CopyWeekRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let from = request.from.week(Weekday::Mon).first_day();
+    let to = request.to.week(Weekday::Mon).first_day();
+
     let conn = state.pool.get().await?;
-    let today = chrono::Local::now().date_naive();
-    let days = db::calendar::get_days(conn, today, today.succ_opt().unwrap()).await?;
+    db::calendar::copy_week(conn, from, to).await?;
+
+    Ok(Json(serde_json::json!({ "result": "success" })))
+}
+
+/// Moves the meals of a day to another day.
+pub async fn move_day_handler(
+    State(state): State<AppState>,
+    Json(request): Json<DayPairRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::calendar::move_day(conn, request.from, request.to, &state.slots).await?;
+
+    Ok(Json(serde_json::json!({ "result": "success" })))
+}
+
+/// Swaps the meals of two days.
+pub async fn swap_days_handler(
+    State(state): State<AppState>,
+    Json(request): Json<DayPairRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::calendar::swap_days(conn, request.from, request.to).await?;
+
+    Ok(Json(serde_json::json!({ "result": "success" })))
+}
+
+/// Moves the foods of a single meal to another meal.
+pub async fn move_meal_handler(
+    State(state): State<AppState>,
+    Json(request): Json<MoveMealRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::calendar::move_meal(conn, &request.from, &request.to, &state.slots).await?;
+
+    Ok(Json(serde_json::json!({ "result": "success" })))
+}
 
-    Ok(Json(days))
+/// Removes all meals within the given range.
+/// Every day of the range is written, so the range is limited to a year.
+pub async fn clear_range_handler(
+    State(state): State<AppState>,
+    Json(request): Json<ClearRangeRequest>,
+) -> Result<Response, AppError> {
+    if request.to < request.from || (request.to - request.from).num_days() > 366 {
+        return Ok((StatusCode::BAD_REQUEST, "Invalid range").into_response());
+    }
+
+    let conn = state.pool.get().await?;
+    db::calendar::clear_range(
+        conn,
+        request.from,
+        request.to.succ_opt().unwrap(),
+        &state.slots,
+    )
+    .await?;
+
+    Ok(Json(serde_json::json!({ "result": "success" })).into_response())
+}
+
+/// Returns the stored day with the given id as JSON.
+pub async fn get_day_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Response, AppError> {
+    let conn = state.pool.get().await?;
+    let day = db::calendar::get_day(conn, id, &state.slots).await?;
+
+    match day {
+        Some(day) => Ok(Json(day).into_response()),
+        None => Ok((StatusCode::NOT_FOUND, "Day not found").into_response()),
+    }
 }
@@ -1,15 +1,19 @@
 use super::AppError;
 use crate::{
     db::{self},
-    food::{EditFood, Food},
+    food::{EditFood, Food, FoodCard},
+    household,
     server::AppState,
 };
 use askama::Template;
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::{Html, IntoResponse},
 };
+use chrono::Months;
+use serde::Deserialize;
+use std::cmp::Reverse;
 
 pub async fn get_all_foods_handler(
     State(state): State<AppState>,
@@ -76,25 +80,87 @@ pub async fn edit_food_handler(
     let conn = state.pool.get().await?;
     let existing_tags = db::food::get_all_existing_tags(conn).await?;
 
-    let edit_food_template = EditFood::edit_food(food, existing_ingredients, existing_tags);
+    let edit_food_template = EditFood::edit(food, existing_ingredients, existing_tags);
 
     Ok(Html(edit_food_template.render()?))
     // Ok(Json(food))
 }
 
+/// The order in which the foods are displayed in the overview.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FoodSort {
+    /// Order in which the foods were created.
+    #[default]
+    Id,
+    Name,
+    /// Foods that were not cooked for the longest time come first.
+    LastCooked,
+    /// Foods that were cooked most often come first.
+    TimesCooked,
+    /// Foods that are planned next come first.
+    NextPlanned,
+}
+
+#[derive(Deserialize)]
+pub struct FoodListParams {
+    sort: Option<FoodSort>,
+    /// The number of months that are considered for counting how often a food was cooked.
+    months: Option<u32>,
+    /// Only show foods every household member is allowed to eat.
+    #[serde(default)]
+    suitable: bool,
+}
+
 pub async fn food_list_handler(
     State(state): State<AppState>,
+    Query(params): Query<FoodListParams>,
 ) -> Result<impl IntoResponse, AppError> {
+    let months = params.months.unwrap_or(3);
+    let today = chrono::Local::now().date_naive();
+    let since = today
+        .checked_sub_months(Months::new(months))
+        .ok_or_else(|| anyhow::anyhow!("Invalid number of months: {}", months))?;
+
     let conn = state.pool.get().await?;
     let foods = db::food::get_all_foods(conn).await?;
+    let conn = state.pool.get().await?;
+    let mut stats = db::calendar::get_food_stats(conn, today, since).await?;
+    let conn = state.pool.get().await?;
+    let members = db::household::get_members(conn).await?;
+
+    let mut foods: Vec<FoodCard> = foods
+        .into_iter()
+        .filter(|food| !params.suitable || household::suitable_for_everyone(&members, food))
+        .map(|food| {
+            let stats = food.id.and_then(|id| stats.remove(&id)).unwrap_or_default();
+            FoodCard {
+                food,
+                stats,
+                months,
+            }
+        })
+        .collect();
+
+    match params.sort.unwrap_or_default() {
+        FoodSort::Id => {}
+        FoodSort::Name => foods.sort_by_key(|f| f.food.name.to_lowercase()),
+        // `None` is smaller than every date, so never cooked foods come first
+        FoodSort::LastCooked => foods.sort_by_key(|f| f.stats.last_cooked),
+        FoodSort::TimesCooked => foods.sort_by_key(|f| Reverse(f.stats.times_cooked)),
+        FoodSort::NextPlanned => {
+            foods.sort_by_key(|f| (f.stats.next_planned.is_none(), f.stats.next_planned))
+        }
+    }
 
     #[derive(Template)]
     #[template(path = "food/list.html")]
     struct FoodList {
-        foods: Vec<Food>,
+        foods: Vec<FoodCard>,
+        months: u32,
     }
 
-    let food_list = FoodList { foods };
+    let food_list = FoodList { foods, months };
 
     Ok(Html(food_list.render()?))
 }
@@ -0,0 +1,67 @@
+use super::AppError;
+use crate::{
+    calendar::MealSlot,
+    db::{self},
+    household::Member,
+    server::AppState,
+};
+use askama::Template;
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::{Html, IntoResponse},
+};
+
+/// This handler returns the html with all household members.
+pub async fn household_handler(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let mut members = db::household::get_members(conn).await?;
+    // The last form creates a new member
+    members.push(Member::default());
+    let conn = state.pool.get().await?;
+    let mut tags = db::food::get_all_existing_tags(conn).await?;
+    tags.sort();
+    let conn = state.pool.get().await?;
+    let mut ingredients = db::food::get_all_ingredient_names(conn).await?;
+    ingredients.sort();
+
+    #[derive(Template)]
+    #[template(path = "household/index.html")]
+    struct Household<'a> {
+        members: Vec<Member>,
+        tags: Vec<String>,
+        ingredients: Vec<String>,
+        slots: &'a [MealSlot],
+    }
+
+    let household = Household {
+        members,
+        tags,
+        ingredients,
+        slots: &state.slots,
+    };
+
+    Ok(Html(household.render()?))
+}
+
+pub async fn update_member_handler(
+    State(state): State<AppState>,
+    Json(member): Json<Member>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let id = db::household::update_member(conn, &member).await?;
+
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
+
+pub async fn delete_member_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::household::delete_member(conn, id).await?;
+
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
@@ -4,10 +4,15 @@ use axum::{
     response::{Html, IntoResponse, Response},
 };
 
+pub mod api;
 pub mod bring;
 pub mod calendar;
 pub mod food;
+pub mod household;
+pub mod planner;
+pub mod recurring;
 pub mod shopping;
+pub mod week_template;
 
 // Error Wrapper for anyhow::Error.
 pub struct AppError(anyhow::Error);
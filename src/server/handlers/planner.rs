@@ -0,0 +1,76 @@
+use super::AppError;
+use crate::{
+    db::{self},
+    food::Food,
+    planner::{self, DayProposal},
+    server::AppState,
+};
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    response::{Html, IntoResponse},
+};
+use chrono::{Days, NaiveDate};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct PlannerParams {
+    from: Option<NaiveDate>,
+    /// The last day that is planned (inclusive).
+    to: Option<NaiveDate>,
+}
+
+/// This handler returns the html with a proposed plan for all empty slots in the given range.
+/// Nothing is stored here. The user accepts the plan which then gets written day by day through
+/// the `/day/update` endpoint.
+pub async fn get_planner_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PlannerParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let today = chrono::Local::now().date_naive();
+    let from = params.from.unwrap_or(today);
+    let to = params
+        .to
+        .unwrap_or_else(|| from.checked_add_days(Days::new(6)).unwrap());
+    let end = to.succ_opt().unwrap();
+
+    // Load the days around the range as well to respect the repetition rule at the borders
+    let margin = Days::new(state.planner.min_repeat_days.into());
+    let context_from = from.checked_sub_days(margin).unwrap();
+    let context_to = end.checked_add_days(margin).unwrap();
+
+    let conn = state.pool.get().await?;
+    let foods = db::food::get_all_foods(conn).await?;
+    let conn = state.pool.get().await?;
+    let stats = db::calendar::get_food_stats(conn, today, today).await?;
+    let conn = state.pool.get().await?;
+    let days = db::calendar::get_days(conn, context_from, context_to, &state.slots).await?;
+
+    let proposals = planner::suggest(
+        &state.planner,
+        &state.slots,
+        &foods,
+        &stats,
+        days,
+        from,
+        end,
+    );
+
+    #[derive(Template)]
+    #[template(path = "planner/index.html")]
+    struct Planner {
+        from: NaiveDate,
+        to: NaiveDate,
+        proposals: Vec<DayProposal>,
+        foods: Vec<Food>,
+    }
+
+    let planner = Planner {
+        from,
+        to,
+        proposals,
+        foods,
+    };
+
+    Ok(Html(planner.render()?))
+}
@@ -0,0 +1,77 @@
+use super::AppError;
+use crate::{
+    calendar::{MealSlot, slot_label},
+    db::{self},
+    food::Food,
+    recurring::RecurringRule,
+    server::AppState,
+};
+use askama::Template;
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::{Html, IntoResponse},
+};
+
+/// This handler returns the html with all recurring rules and a form to create new ones.
+pub async fn rules_handler(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let rules = db::recurring::get_rules(conn).await?;
+    let conn = state.pool.get().await?;
+    let foods = db::food::get_all_foods(conn).await?;
+    let conn = state.pool.get().await?;
+    let mut tags = db::food::get_all_existing_tags(conn).await?;
+    tags.sort();
+
+    #[derive(Template)]
+    #[template(path = "calendar/rules.html")]
+    struct Rules<'a> {
+        rules: Vec<RecurringRule>,
+        foods: Vec<Food>,
+        tags: Vec<String>,
+        slots: &'a [MealSlot],
+    }
+
+    impl Rules<'_> {
+        fn label<'b>(&'b self, slot: &'b str) -> &'b str {
+            slot_label(self.slots, slot)
+        }
+
+        fn food_name(&self, id: &i32) -> &str {
+            self.foods
+                .iter()
+                .find(|f| f.id == Some(*id))
+                .map(|f| f.name.as_str())
+                .unwrap_or("Gelöschtes Rezept")
+        }
+    }
+
+    let rules = Rules {
+        rules,
+        foods,
+        tags,
+        slots: &state.slots,
+    };
+
+    Ok(Html(rules.render()?))
+}
+
+pub async fn update_rule_handler(
+    State(state): State<AppState>,
+    Json(rule): Json<RecurringRule>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let id = db::recurring::update_rule(conn, &rule, &state.slots).await?;
+
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
+
+pub async fn delete_rule_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::recurring::delete_rule(conn, id).await?;
+
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
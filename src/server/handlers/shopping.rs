@@ -1,29 +1,60 @@
-use std::collections::HashMap;
-
 use super::AppError;
 use crate::{
+    calendar::{Day, MealSlot, slot_label, weekday_name},
     db::{self},
+    export,
+    names::{Alias, Names},
     server::AppState,
-    shopping::{EditItem, Item},
+    shopping::{
+        CreateDefaultListRequest, CreateListRequest, DefaultList, EditItem, ListItem,
+        MergeItemsRequest, Package, Packages, RoundPackagesRequest, SectionOverride, Sections,
+        ShoppingList, UpdateDefaultListRequest, collect_items, compress_items,
+    },
 };
 use askama::Template;
 use axum::{
     Json,
     extract::{Path, Query, State},
-    response::{Html, IntoResponse},
+    http::{
+        HeaderMap, StatusCode,
+        header::{ACCEPT, CONTENT_DISPOSITION, CONTENT_TYPE},
+    },
+    response::{Html, IntoResponse, Response},
 };
 use chrono::{Datelike, Days, NaiveDate, NaiveWeek};
 use serde::Deserialize;
+use std::sync::Arc;
+
+/// Renames a default list and replaces its items.
+pub async fn update_default_list_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(request): Json<UpdateDefaultListRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::shopping::update_default_list(conn, id, request.name.trim(), &request.items).await?;
+
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
+
+pub async fn create_default_list_handler(
+    State(state): State<AppState>,
+    Json(request): Json<CreateDefaultListRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let id = db::shopping::create_default_list(conn, request.name.trim()).await?;
+
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
 
-pub async fn update_item_handler(
+pub async fn delete_default_list_handler(
     State(state): State<AppState>,
-    Json(items): Json<Vec<EditItem>>,
+    Path(id): Path<i32>,
 ) -> Result<impl IntoResponse, AppError> {
     let conn = state.pool.get().await?;
-    db::shopping::update_items(conn, &items).await?;
+    db::shopping::delete_default_list(conn, id).await?;
 
-    let result = serde_json::json!({ "result": "success" });
-    Ok(serde_json::to_string(&result)?)
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
 }
 
 pub async fn delete_item_handler(
@@ -41,29 +72,51 @@ pub async fn get_all_items_handler(
     State(state): State<AppState>,
 ) -> Result<impl IntoResponse, AppError> {
     let conn = state.pool.get().await?;
-    let items = db::shopping::get_items(conn).await?;
+    let lists = db::shopping::get_default_lists(conn).await?;
+
+    Ok(serde_json::to_string(&lists)?)
+}
+
+/// This handler returns the html with all default lists.
+pub async fn default_lists_handler(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let lists = db::shopping::get_default_lists(conn).await?;
 
-    Ok(serde_json::to_string(&items)?)
+    #[derive(Template)]
+    #[template(path = "shopping/default_lists.html")]
+    struct DefaultLists {
+        lists: Vec<DefaultList>,
+    }
+
+    Ok(Html(DefaultLists { lists }.render()?))
 }
 
-pub async fn default_item_handler(
+/// This handler returns the html to edit the name and the items of a default list.
+pub async fn edit_default_list_handler(
     State(state): State<AppState>,
+    Path(id): Path<i32>,
 ) -> Result<impl IntoResponse, AppError> {
     let conn = state.pool.get().await?;
-    let items = db::shopping::get_items(conn).await?;
+    let list = db::shopping::get_default_list(conn, id).await?;
     let conn = state.pool.get().await?;
     let existing_items = db::food::get_all_ingredient_names(conn).await?;
 
-    let items = items.into_iter().map(EditItem::from).collect();
+    let items = list.items.into_iter().map(EditItem::from).collect();
 
     #[derive(Template)]
     #[template(path = "shopping/edit_default_list.html")]
     struct EditDefaultItems {
+        id: i32,
+        name: String,
         items: Vec<EditItem>,
         existing_items: Vec<String>,
     }
 
     let edit_default_items = EditDefaultItems {
+        id,
+        name: list.name,
         items,
         existing_items,
     };
@@ -71,13 +124,25 @@ pub async fn default_item_handler(
     Ok(Html(edit_default_items.render()?))
 }
 
-pub async fn get_shopping_index_handler() -> Result<impl IntoResponse, AppError> {
+pub async fn get_shopping_index_handler(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
     #[derive(Template)]
     #[template(path = "shopping/index.html")]
     struct ShoppingMain {
         current_week: Vec<String>,
         next_week: Vec<String>,
         default_date: String,
+        today: NaiveDate,
+        /// The ranges of pushed lists from today on, which are skipped by default.
+        covered: Vec<(NaiveDate, NaiveDate)>,
+        default_lists: Vec<DefaultList>,
+    }
+
+    impl ShoppingMain {
+        fn due_count(&self, list: &DefaultList) -> usize {
+            list.items.iter().filter(|i| i.is_due(self.today)).count()
+        }
     }
 
     let today = chrono::Local::now().date_naive();
@@ -89,10 +154,18 @@ pub async fn get_shopping_index_handler() -> Result<impl IntoResponse, AppError>
         .expect("Could not get next week")
         .week(chrono::Weekday::Mon);
 
+    let conn = state.pool.get().await?;
+    let covered = db::shopping::get_covered_ranges(conn, today, next_week.last_day()).await?;
+    let conn = state.pool.get().await?;
+    let default_lists = db::shopping::get_default_lists(conn).await?;
+
     let shopping_main = ShoppingMain {
         current_week: week_to_day_strings(current_week),
         next_week: week_to_day_strings(next_week),
         default_date: next_saturday.format("%Y-%m-%d").to_string(),
+        today,
+        covered,
+        default_lists,
     };
 
     Ok(Html(shopping_main.render()?))
@@ -127,139 +200,525 @@ fn week_to_day_strings(week: NaiveWeek) -> Vec<String> {
     days
 }
 
-#[derive(Deserialize)]
-pub struct ShoppingListParams {
-    default: Option<bool>,
-    date: Option<String>,
-}
-
-pub async fn shopping_list_handler(
+/// Generates a shopping list for the planned meals and stores it.
+/// The list is shown on its own page afterwards, so it can be edited and reopened.
+pub async fn create_list_handler(
     State(state): State<AppState>,
-    Query(params): Query<ShoppingListParams>,
+    Json(request): Json<CreateListRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    let default = params
-        .default
-        .ok_or_else(|| anyhow::anyhow!("no default provided"))?;
-    let date_str = params
-        .date
-        .as_deref()
-        .ok_or_else(|| anyhow::anyhow!("no date provided"))?;
-    let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")?;
-    let today = chrono::Local::now().date_naive();
+    let now = chrono::Local::now().naive_local();
+    let from = request.from.unwrap_or(now.date());
+    let to = request.to;
+    if to < from {
+        return Err(anyhow::anyhow!("The list has to end after it starts").into());
+    }
+
+    let covered = if request.include_covered {
+        Vec::new()
+    } else {
+        let conn = state.pool.get().await?;
+        db::shopping::get_covered_ranges(conn, from, to).await?
+    };
+    let is_covered = |date: &NaiveDate| covered.iter().any(|(f, t)| f <= date && date <= t);
 
     let mut items = Vec::new();
+    // Default items whose interval has not passed yet are listed, but not checked
+    let mut not_due_ids = Vec::new();
 
-    if default {
+    let mut default_lists = Vec::new();
+    if !request.defaults.is_empty() {
         let conn = state.pool.get().await?;
-        items.extend(db::shopping::get_items(conn).await?);
-    }
-
-    let conn = state.pool.get().await?;
-    let days = db::calendar::get_days(conn, today, date.succ_opt().unwrap()).await?;
-    for day in days {
-        for (food, factor) in day.lunch.iter().chain(day.dinner.iter()) {
-            for ingredient in food.ingredients.iter() {
-                let amount = match &ingredient.amount {
-                    Some((amount, unit)) => {
-                        let amount = amount * factor;
-                        Some((amount, unit.clone()))
-                    }
-                    None => None,
-                };
-
-                let item = Item {
-                    id: None,
-                    name: ingredient.name.clone(),
-                    amount,
-                    order: items.len() as i32,
-                };
-
-                items.push(item);
+        default_lists = db::shopping::get_default_lists(conn)
+            .await?
+            .into_iter()
+            .filter(|list| list.id.is_some_and(|id| request.defaults.contains(&id)))
+            .collect();
+        for item in default_lists.iter().flat_map(|list| list.items.iter()) {
+            if !item.is_due(now.date()) {
+                not_due_ids.extend(item.id);
             }
+            items.push(item.clone());
         }
     }
 
-    let compressed_items = compress_items(&items);
+    let conn = state.pool.get().await?;
+    let (skipped, days): (Vec<Day>, Vec<Day>) =
+        db::calendar::get_days(conn, from, to.succ_opt().unwrap(), &state.slots)
+            .await?
+            .into_iter()
+            .partition(|day| is_covered(&day.date));
+    let conn = state.pool.get().await?;
+    let sources = db::calendar::get_leftover_sources(conn, &days, &state.slots).await?;
+    let optional_items = collect_items(&days, &sources, request.optional, &mut items);
+
+    let conn = state.pool.get().await?;
+    let names = Names::new(db::shopping::get_aliases(conn).await?);
 
-    let bring = state.bring.clone();
-    let lists_resp = bring.get_all_lists().await;
-    let lists_resp = lists_resp.unwrap();
-    let lists = lists_resp.lists;
-    let lists = lists
+    let mut items: Vec<ListItem> = compress_items(&items, &names)
         .into_iter()
-        .map(|list| (list.list_uuid, list.name))
+        .map(|item| {
+            // Checked if a planned food or a due default item needs it
+            let due = !item.sources.is_empty()
+                || item.default_ids.iter().any(|id| !not_due_ids.contains(id));
+            let mut item = ListItem::new(item, false);
+            item.checked = due;
+            item
+        })
+        .chain(
+            compress_items(&optional_items, &names)
+                .into_iter()
+                .map(|item| ListItem::new(item, true)),
+        )
         .collect();
 
+    let conn = state.pool.get().await?;
+    let overrides = db::shopping::get_section_overrides(conn).await?;
+    Sections::new(&state.catalog, &state.shopping, overrides).assign_and_sort(&mut items);
+
+    if request.round_packages {
+        let conn = state.pool.get().await?;
+        let packages = Packages::new(db::shopping::get_packages(conn).await?);
+        for item in items.iter_mut() {
+            item.round_to_package(&packages);
+        }
+    }
+
+    let list = ShoppingList {
+        id: None,
+        from,
+        to,
+        skipped: skipped.iter().map(|day| day.date).collect(),
+        created_at: now,
+        default_lists: default_lists.into_iter().map(|list| list.name).collect(),
+        items,
+        pushed_at: None,
+        pushed_to: None,
+        round_packages: request.round_packages,
+    };
+
+    let conn = state.pool.get().await?;
+    let id = db::shopping::create_list(conn, &list).await?;
+
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
+
+/// This handler returns the html of a stored shopping list.
+pub async fn shopping_list_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let list = db::shopping::get_list(conn, id).await?;
+
+    // The list can still be edited and exported if Bring! is not available
+    let (lists, bring_error) = match &state.bring {
+        Some(bring) => match bring.get_all_lists().await {
+            Ok(resp) => (
+                resp.lists
+                    .into_iter()
+                    .map(|list| (list.list_uuid, list.name))
+                    .collect(),
+                None,
+            ),
+            Err(err) => {
+                eprintln!("Could not load the Bring! lists: {err}");
+                (Vec::new(), Some("Bring! ist gerade nicht erreichbar."))
+            }
+        },
+        None => (Vec::new(), Some("Bring! ist nicht eingerichtet.")),
+    };
+
     #[derive(Template)]
     #[template(path = "shopping/list.html")]
-    struct ShoppingList {
-        items: Vec<FinalItem>,
+    struct ShoppingListPage {
+        list: ShoppingList,
+        /// The lists of the Bring! account the items can be pushed to.
         lists: Vec<(String, String)>,
+        /// Why the items cannot be pushed to Bring!.
+        bring_error: Option<&'static str>,
+        /// The template for items which are added by the user.
+        new_item: ListItem,
+        slots: Arc<Vec<MealSlot>>,
     }
 
-    let shopping_list = ShoppingList {
-        items: compressed_items,
+    impl ShoppingListPage {
+        /// Returns the name of the section, if the item at the index is the first of its section.
+        /// The items are grouped by section when the list is generated.
+        fn section_start(&self, index: &usize) -> Option<String> {
+            let item = &self.list.items[*index];
+            let starts_section = match index.checked_sub(1).map(|i| &self.list.items[i]) {
+                Some(previous) => {
+                    previous.optional != item.optional || previous.section != item.section
+                }
+                None => true,
+            };
+
+            if !starts_section || self.list.items.iter().all(|i| i.section.is_none()) {
+                return None;
+            }
+            Some(
+                item.section
+                    .clone()
+                    .unwrap_or_else(|| "Sonstiges".to_string()),
+            )
+        }
+
+        fn source_label(&self, source: &crate::shopping::Source) -> String {
+            format!(
+                "{}, {} {}",
+                weekday_name(&source.date),
+                source.date.format("%d.%m."),
+                slot_label(&self.slots, &source.slot)
+            )
+        }
+    }
+
+    let shopping_list = ShoppingListPage {
+        list,
         lists,
+        bring_error,
+        new_item: ListItem {
+            name: String::new(),
+            amount: None,
+            checked: true,
+            optional: false,
+            sources: Vec::new(),
+            section: None,
+            default_ids: Vec::new(),
+            needed: None,
+        },
+        slots: state.slots.clone(),
     };
 
     Ok(Html(shopping_list.render()?))
 }
 
-fn compress_items(items: &[Item]) -> Vec<FinalItem> {
-    let mut compressed_items = HashMap::new();
+#[derive(Deserialize)]
+pub struct ExportParams {
+    /// Takes precedence over the `Accept` header.
+    format: Option<export::Format>,
+}
 
-    for item in items {
-        let (order, amounts) = compressed_items
-            .entry(item.name.trim().to_string())
-            .or_insert((item.order, HashMap::new()));
+/// Exports the checked items of a stored shopping list as text, Markdown, CSV or JSON.
+/// The format is taken from the `format` parameter or the `Accept` header and defaults to text.
+pub async fn export_list_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(params): Query<ExportParams>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let conn = state.pool.get().await?;
+    let list = db::shopping::get_list(conn, id).await?;
+
+    let format = params
+        .format
+        .or_else(|| {
+            headers
+                .get(ACCEPT)
+                .and_then(|accept| accept.to_str().ok())
+                .and_then(export::Format::from_accept)
+        })
+        .unwrap_or_default();
+
+    let disposition = format!(
+        "inline; filename=\"einkaufsliste-{}.{}\"",
+        list.from.format("%Y-%m-%d"),
+        format.extension()
+    );
+    Ok((
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (CONTENT_DISPOSITION, disposition),
+        ],
+        export::render(&list, format),
+    )
+        .into_response())
+}
 
-        match &item.amount {
-            Some((amount, unit)) => {
-                *amounts.entry(unit.trim().to_string()).or_insert(0.0) += amount;
-            }
-            None => {}
+/// Stores the edited items of a shopping list.
+pub async fn update_list_items_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(items): Json<Vec<ListItem>>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::shopping::update_list_items(conn, id, &items).await?;
+
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
+
+/// Removes a single source of an item, e.g. because the food is not cooked after all.
+/// The amount of the source is subtracted from the item. The item is removed when neither a
+/// source nor a default item is left.
+pub async fn delete_list_item_source_handler(
+    State(state): State<AppState>,
+    Path((id, item, source)): Path<(i32, usize, usize)>,
+) -> Result<Response, AppError> {
+    let conn = state.pool.get().await?;
+    let mut list = db::shopping::get_list(conn, id).await?;
+    let conn = state.pool.get().await?;
+    let packages = Packages::new(db::shopping::get_packages(conn).await?);
+
+    let Some(list_item) = list.items.get_mut(item) else {
+        return Ok((StatusCode::NOT_FOUND, "Item not found").into_response());
+    };
+    if !list_item.remove_source(source) {
+        return Ok((StatusCode::NOT_FOUND, "Source not found").into_response());
+    }
+
+    if list.round_packages {
+        list_item.round_to_package(&packages);
+    }
+
+    let amount = list_item.amount.clone();
+    let needed = list_item.needed.clone();
+    let removed = list_item.is_unused();
+    if removed {
+        list.items.remove(item);
+    }
+
+    let conn = state.pool.get().await?;
+    db::shopping::update_list_items(conn, id, &list.items).await?;
+
+    Ok(Json(serde_json::json!({
+        "id": id,
+        "result": "success",
+        "amount": amount,
+        "needed": needed,
+        "removed": removed,
+    }))
+    .into_response())
+}
+
+/// Merges two items of a stored shopping list, which mean the same ingredient.
+pub async fn merge_list_items_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(request): Json<MergeItemsRequest>,
+) -> Result<Response, AppError> {
+    let conn = state.pool.get().await?;
+    let mut list = db::shopping::get_list(conn, id).await?;
+
+    let from = list.items.iter().position(|i| i.name == request.from);
+    let into = list.items.iter().position(|i| i.name == request.into);
+    let (Some(from), Some(into)) = (from, into) else {
+        return Ok((StatusCode::NOT_FOUND, "Item not found").into_response());
+    };
+    if from == into {
+        return Ok((StatusCode::BAD_REQUEST, "Cannot merge an item into itself").into_response());
+    }
+
+    let item = list.items.remove(from);
+    let into = if from < into { into - 1 } else { into };
+    list.items[into].merge(item);
+    if list.round_packages {
+        let conn = state.pool.get().await?;
+        let packages = Packages::new(db::shopping::get_packages(conn).await?);
+        list.items[into].round_to_package(&packages);
+    }
+
+    let conn = state.pool.get().await?;
+    db::shopping::update_list_items(conn, id, &list.items).await?;
+
+    if request.alias {
+        let alias = Alias {
+            alias: request.from,
+            name: request.into,
+        };
+        let conn = state.pool.get().await?;
+        db::shopping::set_alias(conn, &alias).await?;
+    }
+
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })).into_response())
+}
+
+/// Rounds the amounts of a stored shopping list up to the package sizes or restores them.
+pub async fn round_packages_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(request): Json<RoundPackagesRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let mut list = db::shopping::get_list(conn, id).await?;
+
+    if request.round {
+        let conn = state.pool.get().await?;
+        let packages = Packages::new(db::shopping::get_packages(conn).await?);
+        for item in list.items.iter_mut() {
+            item.round_to_package(&packages);
         }
-        if item.order < *order {
-            *order = item.order;
+    } else {
+        for item in list.items.iter_mut() {
+            item.unround();
         }
     }
 
-    let mut final_items = Vec::new();
+    let conn = state.pool.get().await?;
+    db::shopping::update_list_items(conn, id, &list.items).await?;
+    let conn = state.pool.get().await?;
+    db::shopping::set_list_round_packages(conn, id, request.round).await?;
 
-    for (name, (order, amounts)) in compressed_items.iter() {
-        let mut amount_str = String::new();
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
 
-        for (unit, quantity) in amounts.iter() {
-            if !unit.is_empty() {
-                if !amount_str.is_empty() {
-                    amount_str.push_str(", ");
-                }
+pub async fn delete_list_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::shopping::delete_list(conn, id).await?;
 
-                amount_str.push_str(&format!("{} {}", quantity, unit));
-            }
-        }
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
+
+/// This handler returns the html with the manually assigned sections of the ingredients.
+pub async fn sections_handler(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let overrides = db::shopping::get_section_overrides(conn).await?;
+    let conn = state.pool.get().await?;
+    let existing_items = db::food::get_all_ingredient_names(conn).await?;
 
-        final_items.push(FinalItem {
-            id: None,
-            name: name.clone(),
-            amount: if amount_str.is_empty() {
-                None
-            } else {
-                Some(amount_str)
-            },
-            order: *order,
-        });
+    let sections = Sections::new(&state.catalog, &state.shopping, Vec::new()).names();
+
+    #[derive(Template)]
+    #[template(path = "shopping/sections.html")]
+    struct SectionsPage {
+        overrides: Vec<SectionOverride>,
+        sections: Vec<String>,
+        existing_items: Vec<String>,
     }
 
-    final_items.sort_by_key(|item| item.order);
+    let page = SectionsPage {
+        overrides,
+        sections,
+        existing_items,
+    };
 
-    final_items
+    Ok(Html(page.render()?))
 }
 
-#[derive(Deserialize)]
-pub struct FinalItem {
-    pub id: Option<i32>,
-    pub name: String,
-    pub amount: Option<String>,
-    pub order: i32,
+pub async fn update_section_handler(
+    State(state): State<AppState>,
+    Json(section_override): Json<SectionOverride>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::shopping::set_section_override(conn, &section_override).await?;
+
+    Ok(Json(serde_json::json!({ "result": "success" })))
+}
+
+pub async fn delete_section_handler(
+    State(state): State<AppState>,
+    Path(ingredient): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::shopping::delete_section_override(conn, &ingredient).await?;
+
+    Ok(Json(serde_json::json!({ "result": "success" })))
+}
+
+/// This handler returns the html with the packages the ingredients are bought in.
+pub async fn packages_handler(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let packages = db::shopping::get_packages(conn).await?;
+    let conn = state.pool.get().await?;
+    let existing_items = db::food::get_all_ingredient_names(conn).await?;
+
+    #[derive(Template)]
+    #[template(path = "shopping/packages.html")]
+    struct PackagesPage {
+        packages: Vec<Package>,
+        existing_items: Vec<String>,
+    }
+
+    let page = PackagesPage {
+        packages,
+        existing_items,
+    };
+
+    Ok(Html(page.render()?))
+}
+
+pub async fn update_package_handler(
+    State(state): State<AppState>,
+    Json(package): Json<Package>,
+) -> Result<Response, AppError> {
+    if package.size <= 0.0 {
+        return Ok((StatusCode::BAD_REQUEST, "The size has to be positive").into_response());
+    }
+
+    let conn = state.pool.get().await?;
+    db::shopping::set_package(conn, &package).await?;
+
+    Ok(Json(serde_json::json!({ "result": "success" })).into_response())
+}
+
+pub async fn delete_package_handler(
+    State(state): State<AppState>,
+    Path(ingredient): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::shopping::delete_package(conn, &ingredient).await?;
+
+    Ok(Json(serde_json::json!({ "result": "success" })))
+}
+
+/// This handler returns the html with the aliases of the ingredient names.
+pub async fn aliases_handler(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let aliases = db::shopping::get_aliases(conn).await?;
+    let conn = state.pool.get().await?;
+    let existing_items = db::food::get_all_ingredient_names(conn).await?;
+
+    #[derive(Template)]
+    #[template(path = "shopping/aliases.html")]
+    struct AliasesPage {
+        aliases: Vec<Alias>,
+        existing_items: Vec<String>,
+    }
+
+    let page = AliasesPage {
+        aliases,
+        existing_items,
+    };
+
+    Ok(Html(page.render()?))
+}
+
+pub async fn update_alias_handler(
+    State(state): State<AppState>,
+    Json(alias): Json<Alias>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::shopping::set_alias(conn, &alias).await?;
+
+    Ok(Json(serde_json::json!({ "result": "success" })))
+}
+
+pub async fn delete_alias_handler(
+    State(state): State<AppState>,
+    Path(alias): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::shopping::delete_alias(conn, &alias).await?;
+
+    Ok(Json(serde_json::json!({ "result": "success" })))
+}
+
+/// This handler returns the html with all stored shopping lists.
+pub async fn history_handler(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let lists = db::shopping::get_lists(conn).await?;
+
+    #[derive(Template)]
+    #[template(path = "shopping/history.html")]
+    struct History {
+        lists: Vec<ShoppingList>,
+    }
+
+    Ok(Html(History { lists }.render()?))
 }
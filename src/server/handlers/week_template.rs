@@ -0,0 +1,125 @@
+use super::AppError;
+use crate::{
+    calendar::{MealSlot, slot_label, weekday_label},
+    db::{self},
+    food::Food,
+    server::AppState,
+    week_template::{ApplyTemplateRequest, SaveTemplateRequest, WeekTemplate},
+};
+use askama::Template;
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::{Html, IntoResponse},
+};
+use chrono::{Days, Weekday};
+
+/// This handler returns the html with all week templates.
+pub async fn templates_handler(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let templates = db::week_template::get_templates(conn).await?;
+    let conn = state.pool.get().await?;
+    let foods = db::food::get_all_foods(conn).await?;
+
+    #[derive(Template)]
+    #[template(path = "calendar/templates.html")]
+    struct Templates<'a> {
+        templates: Vec<WeekTemplate>,
+        foods: Vec<Food>,
+        slots: &'a [MealSlot],
+        current_date: chrono::NaiveDate,
+    }
+
+    impl Templates<'_> {
+        fn label<'b>(&'b self, slot: &'b str) -> &'b str {
+            slot_label(self.slots, slot)
+        }
+
+        fn weekday(&self, weekday: &Weekday) -> &'static str {
+            weekday_label(*weekday)
+        }
+
+        fn food_name(&self, id: &i32) -> &str {
+            self.foods
+                .iter()
+                .find(|f| f.id == Some(*id))
+                .map(|f| f.name.as_str())
+                .unwrap_or("Gelöschtes Rezept")
+        }
+    }
+
+    let templates = Templates {
+        templates,
+        foods,
+        slots: &state.slots,
+        current_date: chrono::Local::now().date_naive(),
+    };
+
+    Ok(Html(templates.render()?))
+}
+
+/// Saves the meals of a week as template. The meals generated by recurring rules are included.
+pub async fn save_template_handler(
+    State(state): State<AppState>,
+    Json(request): Json<SaveTemplateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("The template needs a name").into());
+    }
+
+    let monday = request.week.week(Weekday::Mon).first_day();
+    let conn = state.pool.get().await?;
+    let days = db::calendar::get_days(
+        conn,
+        monday,
+        monday.checked_add_days(Days::new(7)).unwrap(),
+        &state.slots,
+    )
+    .await?;
+
+    let template = WeekTemplate::from_week(name, monday, &days);
+    let conn = state.pool.get().await?;
+    let id = db::week_template::save_template(conn, template).await?;
+
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
+
+/// Applies a template to a week. Every changed day is written through `update_day`.
+pub async fn apply_template_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(request): Json<ApplyTemplateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    let template = db::week_template::get_template(conn, id).await?;
+
+    let monday = request.week.week(Weekday::Mon).first_day();
+    let conn = state.pool.get().await?;
+    let days = db::calendar::get_days(
+        conn,
+        monday,
+        monday.checked_add_days(Days::new(7)).unwrap(),
+        &state.slots,
+    )
+    .await?;
+
+    for day in template.apply(&days, request.mode) {
+        let conn = state.pool.get().await?;
+        db::calendar::update_day(conn, day).await?;
+    }
+
+    Ok(Json(serde_json::json!({ "result": "success" })))
+}
+
+pub async fn delete_template_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = state.pool.get().await?;
+    db::week_template::delete_template(conn, id).await?;
+
+    Ok(Json(serde_json::json!({ "id": id, "result": "success" })))
+}
@@ -1,8 +1,10 @@
-use crate::bring::BringConnection;
-use crate::db;
+use crate::bring::{self, BringConnection, Catalog, LOCALE_DE};
+use crate::calendar::MealSlot;
 use crate::server_config::ServerConfig;
+use crate::{db, ical, planner, shopping};
 use axum::response::Redirect;
 use axum::routing::{get, post};
+use std::sync::Arc;
 
 pub mod handlers;
 
@@ -10,7 +12,14 @@ pub mod handlers;
 #[derive(Clone)]
 pub struct AppState {
     pool: db::ConnectionPool,
-    bring: BringConnection,
+    /// Only set if Bring! is configured.
+    bring: Option<BringConnection>,
+    planner: Arc<planner::Config>,
+    slots: Arc<Vec<MealSlot>>,
+    ical: Option<Arc<ical::Config>>,
+    shopping: Arc<shopping::Config>,
+    /// The Bring! catalog which assigns the items to the sections of the store.
+    catalog: Arc<Catalog>,
 }
 
 /// Initializes the REST API and returns the router.
@@ -18,12 +27,31 @@ pub async fn init_rest_api(config: ServerConfig) -> axum::Router {
     let pool = db::generate_pool(&config.database)
         .await
         .expect("Could not create database pool");
-
-    let bring = BringConnection::login(&config.bring)
+    db::migrate(&pool)
         .await
-        .expect("Could noit create Bring! API connection");
+        .expect("Could not migrate the database");
+
+    // The user is logged in with the first request, so the app also starts while Bring! is down
+    let bring = config.bring.as_ref().map(BringConnection::new);
+
+    // The lists can still be generated without the catalog, they are just not grouped
+    let catalog = match bring::load_catalog(LOCALE_DE).await {
+        Ok(catalog) => Catalog::from(catalog),
+        Err(err) => {
+            eprintln!("Could not load the Bring! catalog: {err}");
+            Catalog::default()
+        }
+    };
 
-    let state = AppState { pool, bring };
+    let state = AppState {
+        pool,
+        bring,
+        planner: Arc::new(config.planner),
+        slots: Arc::new(config.slots),
+        ical: config.ical.map(Arc::new),
+        shopping: Arc::new(config.shopping),
+        catalog: Arc::new(catalog),
+    };
 
     let mut app = axum::Router::new();
 
@@ -46,9 +74,91 @@ pub async fn init_rest_api(config: ServerConfig) -> axum::Router {
     // calendar
     app = app
         .route("/calendar", get(handlers::calendar::get_calendar_handler))
+        .route(
+            "/calendar/month",
+            get(handlers::calendar::get_month_handler),
+        )
+        .route("/calendar.ics", get(handlers::calendar::get_ical_handler))
+        .route("/calendar.pdf", get(handlers::calendar::get_pdf_handler))
+        .route(
+            "/calendar/copy-week",
+            post(handlers::calendar::copy_week_handler),
+        )
+        .route(
+            "/calendar/move-day",
+            post(handlers::calendar::move_day_handler),
+        )
+        .route(
+            "/calendar/swap-days",
+            post(handlers::calendar::swap_days_handler),
+        )
+        .route(
+            "/calendar/move-meal",
+            post(handlers::calendar::move_meal_handler),
+        )
+        .route(
+            "/calendar/clear",
+            post(handlers::calendar::clear_range_handler),
+        )
         .route("/day/update", post(handlers::calendar::update_day_handler))
         .route("/day/{id}", get(handlers::calendar::get_day_handler));
 
+    // JSON API
+    app = app
+        .route("/api/days", get(handlers::api::get_days_handler))
+        .route(
+            "/api/days/{date}",
+            get(handlers::api::get_day_handler)
+                .put(handlers::api::put_day_handler)
+                .delete(handlers::api::delete_day_handler),
+        );
+
+    // recurring rules
+    app = app
+        .route("/rules", get(handlers::recurring::rules_handler))
+        .route(
+            "/rules/update",
+            post(handlers::recurring::update_rule_handler),
+        )
+        .route(
+            "/rules/delete/{id}",
+            post(handlers::recurring::delete_rule_handler),
+        );
+
+    // week templates
+    app = app
+        .route(
+            "/week-templates",
+            get(handlers::week_template::templates_handler),
+        )
+        .route(
+            "/week-templates/save",
+            post(handlers::week_template::save_template_handler),
+        )
+        .route(
+            "/week-templates/apply/{id}",
+            post(handlers::week_template::apply_template_handler),
+        )
+        .route(
+            "/week-templates/delete/{id}",
+            post(handlers::week_template::delete_template_handler),
+        );
+
+    // household
+    app = app
+        .route("/household", get(handlers::household::household_handler))
+        .route(
+            "/household/update",
+            post(handlers::household::update_member_handler),
+        )
+        .route(
+            "/household/delete/{id}",
+            post(handlers::household::delete_member_handler),
+        );
+
+    // planner
+    app = app.route("/planner", get(handlers::planner::get_planner_handler));
+
     // shopping
     app = app
         .route(
@@ -64,20 +174,105 @@ pub async fn init_rest_api(config: ServerConfig) -> axum::Router {
             post(handlers::shopping::delete_item_handler),
         )
         .route(
-            "/shopping/update",
-            post(handlers::shopping::update_item_handler),
+            "/shopping/defaults",
+            get(handlers::shopping::default_lists_handler),
         )
         .route(
-            "/shopping/defaults",
-            get(handlers::shopping::default_item_handler),
+            "/shopping/defaults/create",
+            post(handlers::shopping::create_default_list_handler),
+        )
+        .route(
+            "/shopping/defaults/{id}",
+            get(handlers::shopping::edit_default_list_handler),
+        )
+        .route(
+            "/shopping/defaults/{id}/update",
+            post(handlers::shopping::update_default_list_handler),
+        )
+        .route(
+            "/shopping/defaults/{id}/delete",
+            post(handlers::shopping::delete_default_list_handler),
         )
         .route(
             "/shopping/list",
+            post(handlers::shopping::create_list_handler),
+        )
+        .route(
+            "/shopping/lists/{id}",
             get(handlers::shopping::shopping_list_handler),
+        )
+        .route(
+            "/shopping/lists/{id}/items",
+            post(handlers::shopping::update_list_items_handler),
+        )
+        .route(
+            "/shopping/lists/{id}/export",
+            get(handlers::shopping::export_list_handler),
+        )
+        .route(
+            "/shopping/lists/{id}/items/{item}/sources/{source}/delete",
+            post(handlers::shopping::delete_list_item_source_handler),
+        )
+        .route(
+            "/shopping/lists/{id}/packages",
+            post(handlers::shopping::round_packages_handler),
+        )
+        .route(
+            "/shopping/lists/{id}/merge",
+            post(handlers::shopping::merge_list_items_handler),
+        )
+        .route(
+            "/shopping/lists/{id}/delete",
+            post(handlers::shopping::delete_list_handler),
+        )
+        .route(
+            "/shopping/history",
+            get(handlers::shopping::history_handler),
+        )
+        .route(
+            "/shopping/sections",
+            get(handlers::shopping::sections_handler),
+        )
+        .route(
+            "/shopping/sections/update",
+            post(handlers::shopping::update_section_handler),
+        )
+        .route(
+            "/shopping/sections/delete/{ingredient}",
+            post(handlers::shopping::delete_section_handler),
+        )
+        .route(
+            "/shopping/packages",
+            get(handlers::shopping::packages_handler),
+        )
+        .route(
+            "/shopping/packages/update",
+            post(handlers::shopping::update_package_handler),
+        )
+        .route(
+            "/shopping/packages/delete/{ingredient}",
+            post(handlers::shopping::delete_package_handler),
+        )
+        .route(
+            "/shopping/aliases",
+            get(handlers::shopping::aliases_handler),
+        )
+        .route(
+            "/shopping/aliases/update",
+            post(handlers::shopping::update_alias_handler),
+        )
+        .route(
+            "/shopping/aliases/delete/{alias}",
+            post(handlers::shopping::delete_alias_handler),
         );
 
     // Bring! API
-    app = app.route("/shopping/bring", post(handlers::bring::add_bring_handler));
+    app = app
+        .route("/shopping/bring", post(handlers::bring::add_bring_handler))
+        .route(
+            "/shopping/lists/{id}/push",
+            post(handlers::bring::push_list_handler),
+        );
 
     app.with_state(state)
 }
@@ -1,4 +1,4 @@
-use crate::{bring, db};
+use crate::{bring, calendar, db, ical, planner, shopping};
 use anyhow::{Context, Result};
 use config::{File, FileFormat};
 use serde::Deserialize;
@@ -10,7 +10,17 @@ use std::net::SocketAddr;
 pub struct ServerConfig {
     pub server: Config,
     pub database: db::Config,
-    pub bring: bring::Config,
+    /// Items can only be pushed to Bring! if it is configured with an email and a password.
+    pub bring: Option<bring::Config>,
+    #[serde(default)]
+    pub planner: planner::Config,
+    /// The slots for meals of a day in the order they are displayed.
+    #[serde(default = "calendar::default_slots")]
+    pub slots: Vec<calendar::MealSlot>,
+    /// The iCalendar feed is only available if it is configured.
+    pub ical: Option<ical::Config>,
+    #[serde(default)]
+    pub shopping: shopping::Config,
 }
 
 #[derive(Debug, Deserialize)]
@@ -38,23 +48,39 @@ impl Config {
 /// - `RSSESPLANER_DB_PASSWORD`: The password to connect to the database.
 /// - `RSSESPLANER_DB_NAME`: The name of the database.
 ///
-/// - `RSSESPLANER_BRING_EMAIL`: The email to login to Bring! API.
-/// - `RSSESPLANER_BRING_PASSWORD`: The password to login to Bring! API.
+/// - `RSSESPLANER_BRING_EMAIL`: Optional. The email to login to Bring! API.
+/// - `RSSESPLANER_BRING_PASSWORD`: Optional. The password to login to Bring! API.
+///   Bring! is only used if both are set and not empty.
 ///
-/// Fails if the file cannot be read and any of the variables are not set.
+/// - `RSSESPLANER_ICAL_TOKEN`: Optional. Enables the iCalendar feed with this token.
+///
+/// The rules of the planner, the meal slots and the order of the store sections can only be
+/// configured in the file.
+/// Otherwise the defaults are used.
+///
+/// Fails if the file cannot be read and any of the variables are not set, or if a configured
+/// meal slot has a name that cannot be stored.
 pub fn load_config() -> Result<ServerConfig> {
     // Load the configuration file
     let file_config_res = config::Config::builder()
         .add_source(File::new("config.toml", FileFormat::Toml))
         .build();
 
-    if let Ok(file_config) = file_config_res {
-        let config = file_config.try_deserialize::<ServerConfig>()?;
-        return Ok(config);
-    }
+    let mut config = match file_config_res {
+        Ok(file_config) => file_config.try_deserialize::<ServerConfig>()?,
+        // could not open the file, try to load the configuration from the environment
+        Err(_) => load_from_env()?,
+    };
+
+    // Empty credentials, like the ones of the default config, disable Bring!
+    config.bring = config
+        .bring
+        .filter(|bring| !bring.email.is_empty() && !bring.password.is_empty());
 
-    // could not open the file, try to load the configuration from the environment
-    let config = load_from_env()?;
+    for slot in &config.slots {
+        calendar::validate_slot_name(&slot.name)
+            .with_context(|| format!("Invalid meal slot \"{}\" in the config", slot.label))?;
+    }
 
     Ok(config)
 }
@@ -79,12 +105,20 @@ fn load_from_env() -> Result<ServerConfig> {
             dbname: env::var("RSSESPLANER_DB_NAME")
                 .with_context(|| "RSSESPLANER_DB_NAME not set")?,
         },
-        bring: bring::Config {
-            email: env::var("RSSESPLANER_BRING_EMAIL")
-                .with_context(|| "RSSESPLANER_BRING_EMAIL not set")?,
-            password: env::var("RSSESPLANER_BRING_PASSWORD")
-                .with_context(|| "RSSESPLANER_BRING_PASSWORD not set")?,
+        bring: match (
+            env::var("RSSESPLANER_BRING_EMAIL"),
+            env::var("RSSESPLANER_BRING_PASSWORD"),
+        ) {
+            (Ok(email), Ok(password)) => Some(bring::Config { email, password }),
+            _ => None,
         },
+        planner: planner::Config::default(),
+        slots: calendar::default_slots(),
+        ical: env::var("RSSESPLANER_ICAL_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+            .map(ical::Config::new),
+        shopping: shopping::Config::default(),
     };
 
     Ok(config)
@@ -1,6 +1,20 @@
+use crate::bring::Catalog;
+use crate::calendar::{Day, PlannedFood};
+use crate::names::{self, Names};
 use crate::utils::Amount;
 use askama::Template;
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Configuration of the generated shopping lists.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The sections of the Bring! catalog in the order they are laid out in the supermarket.
+    /// Sections which are not listed follow in the order of the catalog.
+    pub section_order: Vec<String>,
+}
 
 /// This represents the editing view of an item in the shoppinglist
 #[derive(Debug, Serialize, Deserialize, Clone, Template)]
@@ -10,6 +24,10 @@ pub struct EditItem {
     pub name: String,
     pub amount: Option<Amount>,
     pub order: i32,
+    #[serde(default)]
+    pub interval_days: Option<i32>,
+    #[serde(default)]
+    pub last_added: Option<NaiveDate>,
 }
 
 /// This is an Item like it is stored in the database.
@@ -20,6 +38,59 @@ pub struct Item {
     pub name: String,
     pub amount: Option<Amount>,
     pub order: i32,
+    /// The planned food this item is needed for. Default items have no source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    /// Default items with an interval are only needed every few days.
+    #[serde(default)]
+    pub interval_days: Option<i32>,
+    /// When the item was pushed to Bring the last time.
+    #[serde(default)]
+    pub last_added: Option<NaiveDate>,
+}
+
+impl Item {
+    /// Whether the interval of the item has passed since it was added the last time.
+    /// Items without an interval or which were never added are always due.
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        match (self.interval_days, self.last_added) {
+            (Some(interval), Some(last_added)) => {
+                (today - last_added).num_days() >= interval as i64
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Describes which planned food contributed to an item of the shopping list.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Source {
+    pub food: String,
+    pub date: NaiveDate,
+    pub slot: String,
+    /// The amount of the ingredient, already scaled to the planned portions.
+    pub amount: Option<Amount>,
+}
+
+/// A named list of items which are bought regularly, like the weekly shop or the drugstore.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DefaultList {
+    pub id: Option<i32>,
+    pub name: String,
+    pub items: Vec<Item>,
+}
+
+/// Renames a default list and replaces its items.
+#[derive(Deserialize)]
+pub struct UpdateDefaultListRequest {
+    pub name: String,
+    pub items: Vec<EditItem>,
+}
+
+/// Creates a new empty default list.
+#[derive(Deserialize)]
+pub struct CreateDefaultListRequest {
+    pub name: String,
 }
 
 impl From<Item> for EditItem {
@@ -29,6 +100,8 @@ impl From<Item> for EditItem {
             name: item.name,
             amount: item.amount,
             order: item.order,
+            interval_days: item.interval_days,
+            last_added: item.last_added,
         }
     }
 }
@@ -41,6 +114,54 @@ pub struct AddItemsRequest {
     pub items: Vec<BringItem>,
 }
 
+/// Requests to generate and store a shopping list for the planned meals from `from` until `to`.
+#[derive(Deserialize)]
+pub struct CreateListRequest {
+    /// The ids of the default lists whose items are added.
+    #[serde(default)]
+    pub defaults: Vec<i32>,
+    /// The first day whose meals are added. Defaults to today.
+    #[serde(default)]
+    pub from: Option<NaiveDate>,
+    /// The last day whose meals are added (inclusive).
+    #[serde(alias = "date")]
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub optional: OptionalIngredients,
+    /// Also adds the meals of days which are covered by a list that was pushed to Bring already.
+    #[serde(default)]
+    pub include_covered: bool,
+    /// Rounds the amounts up to the defined package sizes.
+    #[serde(default)]
+    pub round_packages: bool,
+}
+
+/// Switches the rounding to package sizes of a stored shopping list.
+#[derive(Deserialize)]
+pub struct RoundPackagesRequest {
+    pub round: bool,
+}
+
+/// Requests to merge an item of a stored shopping list into another one.
+#[derive(Deserialize)]
+pub struct MergeItemsRequest {
+    /// The name of the item which is removed.
+    pub from: String,
+    /// The name of the item it is merged into.
+    pub into: String,
+    /// Also stores an alias, so the names are merged on future lists.
+    #[serde(default)]
+    pub alias: bool,
+}
+
+/// Requests to push the checked items of a stored shopping list to a Bring list.
+#[derive(Deserialize)]
+pub struct PushListRequest {
+    pub list_id: String,
+    /// The name of the Bring list, which is recorded with the push.
+    pub list_name: String,
+}
+
 /// This is a single item that is being added to a shopping list.
 /// Bring does not need all the information that is internally stored in the database.
 #[derive(Deserialize)]
@@ -48,3 +169,838 @@ pub struct BringItem {
     pub name: String,
     pub amount: Option<String>,
 }
+
+/// A generated shopping list like it is stored in the database.
+/// The items keep the state the user edited them to, so the list can be reopened later.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShoppingList {
+    pub id: Option<i32>,
+    /// The first day whose meals are on the list.
+    pub from: NaiveDate,
+    /// The last day whose meals are on the list (inclusive).
+    pub to: NaiveDate,
+    /// The days in the range which were skipped, because a pushed list covers them already.
+    pub skipped: Vec<NaiveDate>,
+    pub created_at: NaiveDateTime,
+    /// The names of the default lists whose items were added to the list.
+    pub default_lists: Vec<String>,
+    pub items: Vec<ListItem>,
+    /// When the list was pushed to Bring the last time.
+    pub pushed_at: Option<NaiveDateTime>,
+    /// The name of the Bring list the items were pushed to.
+    pub pushed_to: Option<String>,
+    /// Whether the amounts are rounded up to the package sizes.
+    pub round_packages: bool,
+}
+
+impl ShoppingList {
+    /// Pairs of item names which are not merged, but look like they mean the same ingredient.
+    pub fn merge_suggestions(&self) -> Vec<(String, String)> {
+        let mut suggestions = Vec::new();
+
+        for (i, a) in self.items.iter().enumerate() {
+            for b in &self.items[i + 1..] {
+                if names::similar(&a.name, &b.name) {
+                    suggestions.push((a.name.clone(), b.name.clone()));
+                }
+            }
+        }
+
+        suggestions
+    }
+
+    pub fn checked_count(&self) -> usize {
+        self.items.iter().filter(|i| i.checked).count()
+    }
+
+    pub fn has_optional_items(&self) -> bool {
+        self.items.iter().any(|i| i.optional)
+    }
+}
+
+/// A single item of a stored shopping list.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ListItem {
+    pub name: String,
+    pub amount: Option<String>,
+    /// Only checked items are pushed to Bring.
+    pub checked: bool,
+    /// Optional ingredients are listed separately and are unchecked initially.
+    #[serde(default)]
+    pub optional: bool,
+    /// The planned foods this item is needed for.
+    #[serde(default)]
+    pub sources: Vec<Source>,
+    /// The section of the store the item can be found in.
+    #[serde(default)]
+    pub section: Option<String>,
+    /// The ids of the default items which were merged into this item.
+    /// Their last added date is updated when the list is pushed.
+    #[serde(default)]
+    pub default_ids: Vec<i32>,
+    /// The amount that is actually needed, if `amount` was rounded up to whole packages.
+    #[serde(default)]
+    pub needed: Option<String>,
+}
+
+impl ListItem {
+    pub fn new(item: FinalItem, optional: bool) -> Self {
+        ListItem {
+            name: item.name,
+            amount: item.amount,
+            checked: !optional,
+            optional,
+            sources: item.sources,
+            section: None,
+            default_ids: item.default_ids,
+            needed: None,
+        }
+    }
+
+    /// Rounds the amount up to whole packages, if a package is defined for the item.
+    pub fn round_to_package(&mut self, packages: &Packages) {
+        if self.needed.is_some() {
+            return;
+        }
+        let Some(package) = packages.get(&self.name) else {
+            return;
+        };
+        let Some(amounts) = self.amount.as_deref().and_then(parse_amounts) else {
+            return;
+        };
+
+        if let Some(rounded) = package.round(&amounts) {
+            self.needed = self.amount.replace(rounded);
+        }
+    }
+
+    /// Restores the needed amount, if the amount was rounded up to whole packages.
+    pub fn unround(&mut self) {
+        if let Some(needed) = self.needed.take() {
+            self.amount = Some(needed);
+        }
+    }
+
+    /// Removes a source and subtracts its amount, so the share of merged default items is kept.
+    /// Rounded amounts are restored first, so they have to be rounded again afterwards.
+    /// Returns false if the index does not exist.
+    pub fn remove_source(&mut self, index: usize) -> bool {
+        if index >= self.sources.len() {
+            return false;
+        }
+
+        let source = self.sources.remove(index);
+        self.unround();
+        let Some(removed) = source.amount else {
+            return true;
+        };
+
+        match self.amount.as_deref().and_then(parse_amounts) {
+            Some(amounts) => self.amount = subtract_amount(amounts, &removed),
+            // An amount edited by hand cannot be split up, it is only recomputed if it
+            // consists of the sources alone
+            None if self.default_ids.is_empty() => {
+                self.amount = sum_amounts(self.sources.iter().filter_map(|s| s.amount.as_ref()));
+            }
+            None => {}
+        }
+        true
+    }
+
+    /// Whether the item is neither needed for a planned food nor a default item anymore.
+    pub fn is_unused(&self) -> bool {
+        self.sources.is_empty() && self.default_ids.is_empty()
+    }
+
+    /// Adds the amounts, sources and default items of the other item to this one.
+    /// Amounts which cannot be summed up are joined with a plus.
+    /// Rounded amounts are restored first, so they have to be rounded again afterwards.
+    pub fn merge(&mut self, mut other: ListItem) {
+        self.unround();
+        other.unround();
+        self.amount = match (self.amount.take(), other.amount) {
+            (Some(a), Some(b)) => match (parse_amounts(&a), parse_amounts(&b)) {
+                (Some(mut x), Some(y)) => {
+                    x.extend(y);
+                    sum_amounts(x.iter())
+                }
+                _ => Some(format!("{} + {}", a, b)),
+            },
+            (a, b) => a.or(b),
+        };
+        self.checked |= other.checked;
+        self.optional &= other.optional;
+        self.sources.extend(other.sources);
+        self.default_ids.extend(other.default_ids);
+    }
+
+    /// The sources as JSON, so they can be sent back when the list is saved.
+    pub fn sources_json(&self) -> String {
+        serde_json::to_string(&self.sources).unwrap_or_else(|_| "[]".to_string())
+    }
+}
+
+/// A package an ingredient is bought in, like a "Packung" of 200 g feta.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Package {
+    pub ingredient: String,
+    /// The name of the package, like "Packung", "Dose" or "Tube".
+    pub name: String,
+    pub size: f32,
+    pub unit: String,
+}
+
+impl Package {
+    /// Rounds the amounts with the unit of the package up to whole packages,
+    /// e.g. "1 Packung (200 g)". The amounts with other units are kept as they are.
+    /// Returns `None` if no amount has the unit of the package.
+    pub fn round(&self, amounts: &[Amount]) -> Option<String> {
+        let (size, unit) = base_unit(self.size, &self.unit);
+        if size <= 0.0 {
+            return None;
+        }
+
+        let mut needed = 0.0;
+        let mut others = Vec::new();
+        for (quantity, amount_unit) in amounts {
+            let (quantity, amount_unit) = base_unit(*quantity, amount_unit);
+            if amount_unit == unit {
+                needed += quantity;
+            } else {
+                others.push((quantity, amount_unit));
+            }
+        }
+        if needed <= 0.0 {
+            return None;
+        }
+
+        // Scaled amounts are not exact, e.g. 175 g * 8/7 = 200.00002 g still fits one package
+        let count = ((needed / size) - 1e-4).ceil().max(1.0);
+        let rounded = format!("{} {} ({} {})", count, self.name, self.size, self.unit);
+        Some(match sum_amounts(others.iter()) {
+            Some(others) => format!("{}, {}", rounded, others),
+            None => rounded,
+        })
+    }
+}
+
+/// Converts kilograms and liters, so they can be compared with grams and milliliters.
+fn base_unit(quantity: f32, unit: &str) -> (f32, String) {
+    let unit = unit.trim();
+    match unit.to_lowercase().as_str() {
+        "kg" => (quantity * 1000.0, "g".to_string()),
+        "l" => (quantity * 1000.0, "ml".to_string()),
+        "g" | "ml" => (quantity, unit.to_lowercase()),
+        _ => (quantity, unit.to_string()),
+    }
+}
+
+/// The defined packages by the normalized name of their ingredient.
+#[derive(Debug, Default)]
+pub struct Packages(HashMap<String, Package>);
+
+impl Packages {
+    pub fn new(packages: Vec<Package>) -> Self {
+        Packages(
+            packages
+                .into_iter()
+                .map(|package| (names::normalize(&package.ingredient), package))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Package> {
+        self.0.get(&names::normalize(name))
+    }
+}
+
+/// A manually assigned section for an ingredient, which takes precedence over the catalog.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SectionOverride {
+    pub ingredient: String,
+    pub section: String,
+}
+
+/// Assigns items to the sections of the store and sorts them in the configured order.
+pub struct Sections<'a> {
+    catalog: &'a Catalog,
+    order: &'a [String],
+    /// The overridden sections by the normalized name of the ingredient.
+    overrides: HashMap<String, String>,
+}
+
+impl<'a> Sections<'a> {
+    pub fn new(catalog: &'a Catalog, config: &'a Config, overrides: Vec<SectionOverride>) -> Self {
+        Sections {
+            catalog,
+            order: &config.section_order,
+            overrides: overrides
+                .into_iter()
+                .map(|o| (names::normalize(&o.ingredient), o.section))
+                .collect(),
+        }
+    }
+
+    /// All known sections in the configured order.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.order.to_vec();
+        for section in &self.catalog.sections {
+            if !names.contains(section) {
+                names.push(section.clone());
+            }
+        }
+        names
+    }
+
+    pub fn section_of(&self, name: &str) -> Option<String> {
+        match self.overrides.get(&names::normalize(name)) {
+            Some(section) => Some(section.clone()),
+            None => self.catalog.section_of(name).map(str::to_string),
+        }
+    }
+
+    /// The position of the section when the items are sorted.
+    /// Items without a section come last.
+    fn rank(&self, section: Option<&str>) -> usize {
+        let unknown = self.order.len() + self.catalog.sections.len();
+        let Some(section) = section else {
+            return unknown + 1;
+        };
+
+        self.order
+            .iter()
+            .position(|s| s == section)
+            .or_else(|| {
+                self.catalog
+                    .sections
+                    .iter()
+                    .position(|s| s == section)
+                    .map(|i| self.order.len() + i)
+            })
+            .unwrap_or(unknown)
+    }
+
+    /// Sets the section of every item and groups them by section.
+    /// The optional items stay behind the other ones and the order within a section is kept.
+    pub fn assign_and_sort(&self, items: &mut [ListItem]) {
+        for item in items.iter_mut() {
+            item.section = self.section_of(&item.name);
+        }
+        items.sort_by_key(|item| (item.optional, self.rank(item.section.as_deref())));
+    }
+}
+
+/// Describes how optional ingredients of the planned foods end up on the shopping list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionalIngredients {
+    /// Optional ingredients are left out completely.
+    #[default]
+    Exclude,
+    /// Optional ingredients are listed in a separate section and are unchecked.
+    Separate,
+    /// Optional ingredients are treated like every other ingredient.
+    Include,
+}
+
+/// Adds the scaled ingredients of all planned foods of the days to `items`.
+/// `sources` are the days with the meals the leftovers belong to, see
+/// `db::calendar::get_leftover_sources`.
+/// Returns the optional ingredients which are listed separately.
+pub fn collect_items(
+    days: &[Day],
+    sources: &[Day],
+    optional: OptionalIngredients,
+    items: &mut Vec<Item>,
+) -> Vec<Item> {
+    let mut optional_items = Vec::new();
+
+    // Leftovers are covered by the meal they belong to, even if it is on an earlier list
+    let planned_foods = days.iter().flat_map(|day| {
+        day.meals.iter().flat_map(move |meal| {
+            meal.foods
+                .iter()
+                .filter(|p| !is_cooked_in(sources, p))
+                .map(move |planned| (day.date, &meal.slot, planned))
+        })
+    });
+
+    for (date, slot, planned) in planned_foods {
+        let factor = planned.factor;
+        for ingredient in planned.food.ingredients.iter() {
+            let target = match (ingredient.optional, optional) {
+                (false, _) | (true, OptionalIngredients::Include) => &mut *items,
+                (true, OptionalIngredients::Separate) => &mut optional_items,
+                (true, OptionalIngredients::Exclude) => continue,
+            };
+
+            let amount = ingredient
+                .amount
+                .as_ref()
+                .map(|(amount, unit)| (amount * factor, unit.clone()));
+
+            let item = Item {
+                id: None,
+                name: ingredient.name.clone(),
+                amount: amount.clone(),
+                order: target.len() as i32,
+                source: Some(Source {
+                    food: planned.food.name.clone(),
+                    date,
+                    slot: slot.clone(),
+                    amount,
+                }),
+                interval_days: None,
+                last_added: None,
+            };
+
+            target.push(item);
+        }
+    }
+
+    optional_items
+}
+
+/// Whether the food is leftovers of a meal of the days, which still cooks the same food.
+/// Leftovers of a meal that was removed are not covered by it.
+fn is_cooked_in(days: &[Day], planned: &PlannedFood) -> bool {
+    let Some(meal_ref) = &planned.leftovers_of else {
+        return false;
+    };
+
+    days.iter()
+        .filter(|day| day.date == meal_ref.date)
+        .flat_map(|day| day.meals.iter())
+        .filter(|meal| meal.slot == meal_ref.slot)
+        .flat_map(|meal| meal.foods.iter())
+        .any(|cooked| !cooked.is_leftovers() && cooked.food.id == planned.food.id)
+}
+
+/// Merges items with the same name and sums up their amounts per unit.
+/// The names are matched by `Names`, the merged item is listed under the name of the alias
+/// or the first spelling otherwise.
+pub fn compress_items(items: &[Item], names: &Names) -> Vec<FinalItem> {
+    let mut compressed_items: HashMap<String, (String, i32, Vec<&Item>)> = HashMap::new();
+
+    for item in items {
+        let (key, alias) = names.resolve(&item.name);
+        let (_, order, merged) = compressed_items.entry(key).or_insert((
+            alias.unwrap_or(item.name.trim()).to_string(),
+            item.order,
+            Vec::new(),
+        ));
+
+        merged.push(item);
+        if item.order < *order {
+            *order = item.order;
+        }
+    }
+
+    let mut final_items = Vec::new();
+
+    for (name, order, merged) in compressed_items.into_values() {
+        final_items.push(FinalItem {
+            id: None,
+            name,
+            amount: sum_amounts(merged.iter().filter_map(|item| item.amount.as_ref())),
+            order,
+            sources: merged
+                .iter()
+                .filter_map(|item| item.source.clone())
+                .collect(),
+            default_ids: merged.iter().filter_map(|item| item.id).collect(),
+        });
+    }
+
+    final_items.sort_by_key(|item| item.order);
+
+    final_items
+}
+
+/// Parses amounts formatted by `sum_amounts` back.
+/// Amounts joined with a plus by `ListItem::merge` cannot be parsed.
+fn parse_amounts(amounts: &str) -> Option<Vec<Amount>> {
+    amounts
+        .split(", ")
+        .map(|amount| {
+            let (quantity, unit) = amount.trim().split_once(' ')?;
+            if unit.contains('+') {
+                return None;
+            }
+            Some((quantity.parse().ok()?, unit.to_string()))
+        })
+        .collect()
+}
+
+/// Subtracts the amount from the amount with the same unit and formats the remaining amounts.
+/// Units that are used up are left out.
+fn subtract_amount(amounts: Vec<Amount>, (quantity, unit): &Amount) -> Option<String> {
+    let remaining: Vec<Amount> = amounts
+        .into_iter()
+        .map(|(q, u)| {
+            if u.trim() == unit.trim() {
+                // Rounded, so the remaining amount does not show float errors
+                (((q - quantity) * 1000.0).round() / 1000.0, u)
+            } else {
+                (q, u)
+            }
+        })
+        .filter(|(q, _)| *q > 0.0)
+        .collect();
+
+    sum_amounts(remaining.iter())
+}
+
+/// Sums up the amounts per unit and formats them like "200 g, 2 Stück".
+/// Amounts without a unit are left out.
+fn sum_amounts<'a>(amounts: impl Iterator<Item = &'a Amount>) -> Option<String> {
+    let mut summed: Vec<(&str, f32)> = Vec::new();
+
+    for (quantity, unit) in amounts {
+        let unit = unit.trim();
+        match summed.iter_mut().find(|(u, _)| *u == unit) {
+            Some((_, sum)) => *sum += quantity,
+            None => summed.push((unit, *quantity)),
+        }
+    }
+
+    let amount_str = summed
+        .into_iter()
+        .filter(|(unit, _)| !unit.is_empty())
+        .map(|(unit, quantity)| format!("{} {}", quantity, unit))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if amount_str.is_empty() {
+        None
+    } else {
+        Some(amount_str)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct FinalItem {
+    pub id: Option<i32>,
+    pub name: String,
+    pub amount: Option<String>,
+    pub order: i32,
+    /// The planned foods which contributed to this item.
+    #[serde(default)]
+    pub sources: Vec<Source>,
+    /// The ids of the default items which contributed to this item.
+    #[serde(default)]
+    pub default_ids: Vec<i32>,
+}
+
+impl From<ListItem> for FinalItem {
+    fn from(item: ListItem) -> Self {
+        FinalItem {
+            id: None,
+            name: item.name,
+            amount: item.amount,
+            order: 0,
+            sources: item.sources,
+            default_ids: item.default_ids,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::{Meal, MealRef};
+    use crate::food::{Food, Ingredient};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    fn planned(leftovers_of: Option<MealRef>) -> PlannedFood {
+        PlannedFood {
+            food: Food {
+                id: Some(1),
+                name: "Chili".to_string(),
+                tags: Vec::new(),
+                details: String::new(),
+                portions: 4,
+                ingredients: vec![Ingredient {
+                    id: None,
+                    name: "Bohnen".to_string(),
+                    amount: Some((400.0, "g".to_string())),
+                    optional: false,
+                }],
+            },
+            factor: 1.0,
+            leftovers_of,
+            from_rule: None,
+        }
+    }
+
+    fn day(day: u32, foods: Vec<PlannedFood>) -> Day {
+        Day {
+            id: None,
+            date: date(day),
+            meals: vec![Meal {
+                slot: "dinner".to_string(),
+                foods,
+                headcount: None,
+            }],
+            note: String::new(),
+        }
+    }
+
+    fn leftovers_of(day: u32) -> Option<MealRef> {
+        Some(MealRef {
+            date: date(day),
+            slot: "dinner".to_string(),
+        })
+    }
+
+    fn source(quantity: f32, unit: &str) -> Source {
+        Source {
+            food: "Chili".to_string(),
+            date: date(3),
+            slot: "dinner".to_string(),
+            amount: Some((quantity, unit.to_string())),
+        }
+    }
+
+    fn list_item(amount: &str, sources: Vec<Source>, default_ids: Vec<i32>) -> ListItem {
+        ListItem {
+            name: "Bohnen".to_string(),
+            amount: Some(amount.to_string()),
+            checked: true,
+            optional: false,
+            sources,
+            section: None,
+            default_ids,
+            needed: None,
+        }
+    }
+
+    #[test]
+    fn amounts_are_summed_per_unit() {
+        let amounts = [
+            (200.0, "g".to_string()),
+            (2.0, "Stück".to_string()),
+            (100.0, " g ".to_string()),
+        ];
+
+        assert_eq!(
+            sum_amounts(amounts.iter()),
+            Some("300 g, 2 Stück".to_string())
+        );
+    }
+
+    #[test]
+    fn amounts_without_unit_are_left_out() {
+        let amounts = [(2.0, String::new())];
+
+        assert_eq!(sum_amounts(amounts.iter()), None);
+    }
+
+    #[test]
+    fn removing_a_source_subtracts_its_amount() {
+        let mut item = list_item(
+            "600 g",
+            vec![source(400.0, "g"), source(200.0, "g")],
+            vec![],
+        );
+
+        assert!(item.remove_source(0));
+        assert_eq!(item.amount.as_deref(), Some("200 g"));
+        assert!(!item.is_unused());
+
+        assert!(item.remove_source(0));
+        assert_eq!(item.amount, None);
+        assert!(item.is_unused());
+    }
+
+    #[test]
+    fn removing_a_source_keeps_the_default_share() {
+        // 400 g for the chili and 500 g from a default list
+        let mut item = list_item("900 g", vec![source(400.0, "g")], vec![3]);
+
+        assert!(item.remove_source(0));
+        assert_eq!(item.amount.as_deref(), Some("500 g"));
+        assert!(!item.is_unused());
+    }
+
+    #[test]
+    fn removing_a_source_restores_rounded_amounts() {
+        let mut item = list_item("2 Dose (800 g)", vec![source(400.0, "g")], vec![3]);
+        item.needed = Some("900 g".to_string());
+
+        assert!(item.remove_source(0));
+        assert_eq!(item.amount.as_deref(), Some("500 g"));
+        assert_eq!(item.needed, None);
+    }
+
+    #[test]
+    fn removing_a_missing_source_fails() {
+        let mut item = list_item("400 g", vec![source(400.0, "g")], vec![]);
+
+        assert!(!item.remove_source(1));
+        assert_eq!(item.amount.as_deref(), Some("400 g"));
+    }
+
+    #[test]
+    fn amounts_are_parsed_back() {
+        assert_eq!(
+            parse_amounts("300 g, 2 Stück"),
+            Some(vec![(300.0, "g".to_string()), (2.0, "Stück".to_string())])
+        );
+        assert_eq!(parse_amounts("1.5 kg"), Some(vec![(1.5, "kg".to_string())]));
+    }
+
+    #[test]
+    fn amounts_edited_by_hand_are_not_parsed() {
+        assert_eq!(parse_amounts("etwas"), None);
+        assert_eq!(parse_amounts("2 Dosen + etwas"), None);
+        assert_eq!(parse_amounts(""), None);
+    }
+
+    #[test]
+    fn section_overrides_match_other_spellings() {
+        let catalog = Catalog::default();
+        let config = Config::default();
+        let sections = Sections::new(
+            &catalog,
+            &config,
+            vec![SectionOverride {
+                ingredient: "tomaten".to_string(),
+                section: "Gemüse".to_string(),
+            }],
+        );
+
+        assert_eq!(sections.section_of("Tomate").as_deref(), Some("Gemüse"));
+        assert_eq!(sections.section_of(" TOMATEN ").as_deref(), Some("Gemüse"));
+        assert_eq!(sections.section_of("Gurke"), None);
+    }
+
+    fn cream() -> Package {
+        Package {
+            ingredient: "Sahne".to_string(),
+            name: "Becher".to_string(),
+            size: 200.0,
+            unit: "g".to_string(),
+        }
+    }
+
+    #[test]
+    fn amounts_are_rounded_up_to_whole_packages() {
+        let amounts = [(250.0, "g".to_string())];
+
+        assert_eq!(cream().round(&amounts).as_deref(), Some("2 Becher (200 g)"));
+    }
+
+    #[test]
+    fn exact_amounts_are_not_rounded_up() {
+        let amounts = [(0.6, "kg".to_string())];
+
+        assert_eq!(cream().round(&amounts).as_deref(), Some("3 Becher (200 g)"));
+    }
+
+    #[test]
+    fn scaled_amounts_are_not_rounded_up() {
+        // 175 g * 8/7 is slightly more than 200 g
+        let factor = 8.0 / 7.0;
+        let amounts = [(175.0 * factor, "g".to_string())];
+
+        assert_eq!(cream().round(&amounts).as_deref(), Some("1 Becher (200 g)"));
+    }
+
+    #[test]
+    fn kilograms_and_liters_are_converted() {
+        let amounts = [(0.3, "kg".to_string()), (100.0, "G".to_string())];
+        assert_eq!(cream().round(&amounts).as_deref(), Some("2 Becher (200 g)"));
+
+        let milk = Package {
+            ingredient: "Milch".to_string(),
+            name: "Flasche".to_string(),
+            size: 1.0,
+            unit: "l".to_string(),
+        };
+        let amounts = [(1500.0, "ml".to_string())];
+        assert_eq!(milk.round(&amounts).as_deref(), Some("2 Flasche (1 l)"));
+    }
+
+    #[test]
+    fn other_units_are_kept() {
+        let amounts = [(100.0, "g".to_string()), (2.0, "EL".to_string())];
+
+        assert_eq!(
+            cream().round(&amounts).as_deref(),
+            Some("1 Becher (200 g), 2 EL")
+        );
+        assert_eq!(cream().round(&[(2.0, "EL".to_string())]), None);
+    }
+
+    #[test]
+    fn packages_match_other_spellings() {
+        let packages = Packages::new(vec![cream()]);
+
+        assert!(packages.get("sahne").is_some());
+        assert!(packages.get("Milch").is_none());
+    }
+
+    fn default_item(interval_days: Option<i32>, last_added: Option<NaiveDate>) -> Item {
+        Item {
+            id: Some(3),
+            name: "Kaffee".to_string(),
+            amount: None,
+            order: 0,
+            source: None,
+            interval_days,
+            last_added,
+        }
+    }
+
+    #[test]
+    fn items_are_due_after_their_interval() {
+        let item = default_item(Some(7), Some(date(3)));
+
+        assert!(!item.is_due(date(9)));
+        assert!(item.is_due(date(10)));
+    }
+
+    #[test]
+    fn items_without_interval_or_never_added_are_due() {
+        assert!(default_item(None, Some(date(3))).is_due(date(3)));
+        assert!(default_item(Some(7), None).is_due(date(3)));
+    }
+
+    #[test]
+    fn leftovers_of_a_collected_meal_are_skipped() {
+        let days = [
+            day(3, vec![planned(None)]),
+            day(4, vec![planned(leftovers_of(3))]),
+        ];
+        let mut items = Vec::new();
+        collect_items(&days, &days[..1], OptionalIngredients::Exclude, &mut items);
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].source.as_ref().unwrap().date, date(3));
+    }
+
+    #[test]
+    fn leftovers_of_a_meal_before_the_days_are_skipped() {
+        let days = [day(4, vec![planned(leftovers_of(3))])];
+        let sources = [day(3, vec![planned(None)])];
+        let mut items = Vec::new();
+        collect_items(&days, &sources, OptionalIngredients::Exclude, &mut items);
+
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn leftovers_of_a_removed_meal_are_counted() {
+        let days = [day(4, vec![planned(leftovers_of(3))])];
+        let sources = [day(3, Vec::new())];
+        let mut items = Vec::new();
+        collect_items(&days, &sources, OptionalIngredients::Exclude, &mut items);
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].source.as_ref().unwrap().date, date(4));
+    }
+}
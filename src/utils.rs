@@ -1,7 +1,7 @@
 use anyhow::Result;
 
 /// Converts a ';'-seperated string to a vector of strings.
-pub fn string_to_vec(s: &String) -> Vec<String> {
+pub fn string_to_vec(s: &str) -> Vec<String> {
     s.split("; ")
         .filter_map(|s| match s.trim() {
             "" => None,
@@ -11,7 +11,7 @@ pub fn string_to_vec(s: &String) -> Vec<String> {
 }
 
 /// Converts a vector of strings to a ';'-seperated string.
-pub fn vec_to_string(v: &Vec<String>) -> String {
+pub fn vec_to_string(v: &[String]) -> String {
     v.join("; ")
 }
 
@@ -35,10 +35,8 @@ pub fn string_to_amount_unit(s: &Option<String>) -> Result<Option<Amount>> {
 /// Converts an `Amount` and unit tuple to a string.
 /// Example: (1.0, "kg".to_string()) -> "1.0; kg"
 pub fn amount_unit_to_string(s: &Option<Amount>) -> Option<String> {
-    match s {
-        Some((quantity, unit)) => Some(format!("{}; {}", quantity, unit)),
-        None => None,
-    }
+    s.as_ref()
+        .map(|(quantity, unit)| format!("{}; {}", quantity, unit))
 }
 
 /// Holds the quantity and unit of an item.
@@ -0,0 +1,128 @@
+use crate::calendar::{Day, ResponseDay, ResponseMeal};
+use crate::db::calendar::shift_leftovers;
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// A named week plan which can be applied to any week of the calendar.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WeekTemplate {
+    pub id: Option<i32>,
+    pub name: String,
+    pub days: Vec<TemplateDay>,
+}
+
+/// The meals of a single weekday of a template.
+///
+/// Leftovers reference a meal within the week of 2001-01-01, so they can be moved to the week the
+/// template gets applied to.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TemplateDay {
+    pub weekday: Weekday,
+    pub meals: Vec<ResponseMeal>,
+}
+
+/// How a template is applied to a week that already has planned meals.
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplyMode {
+    /// The meals of the template replace the planned meals of the same slot.
+    Overwrite,
+    /// Only slots without planned foods are filled.
+    #[default]
+    FillEmpty,
+}
+
+/// The monday of the week the leftovers of templates refer to.
+fn reference_monday() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2001, 1, 1).unwrap()
+}
+
+impl WeekTemplate {
+    /// Creates a template from the days of the week starting at `monday`.
+    /// Empty slots are stored as well, so overwriting with the template clears them.
+    pub fn from_week(name: String, monday: NaiveDate, days: &[Day]) -> Self {
+        let offset = reference_monday() - monday;
+
+        let days = days
+            .iter()
+            .filter(|day| day.date >= monday && (day.date - monday).num_days() < 7)
+            .map(|day| TemplateDay {
+                weekday: day.date.weekday(),
+                meals: day
+                    .meals
+                    .iter()
+                    .map(|meal| shift_leftovers(ResponseMeal::from(meal), |d| d + offset))
+                    .collect(),
+            })
+            .collect();
+
+        WeekTemplate {
+            id: None,
+            name,
+            days,
+        }
+    }
+
+    /// Merges the template into the given days of a week and returns the days that have to be
+    /// written. Slots that are not part of the template are kept as they are.
+    pub fn apply(&self, days: &[Day], mode: ApplyMode) -> Vec<ResponseDay> {
+        let mut result = Vec::new();
+
+        for day in days {
+            let Some(template_day) = self.days.iter().find(|d| d.weekday == day.date.weekday())
+            else {
+                continue;
+            };
+            let monday = day.date.week(Weekday::Mon).first_day();
+            let offset = monday - reference_monday();
+
+            let mut meals: Vec<ResponseMeal> = day.meals.iter().map(ResponseMeal::from).collect();
+
+            let mut changed = false;
+            for template_meal in &template_day.meals {
+                let template_meal = shift_leftovers(template_meal.clone(), |d| d + offset);
+
+                match meals.iter_mut().find(|m| m.slot == template_meal.slot) {
+                    Some(meal) if mode == ApplyMode::Overwrite || meal.foods.is_empty() => {
+                        meal.foods = template_meal.foods;
+                        if template_meal.headcount.is_some() {
+                            meal.headcount = template_meal.headcount;
+                        }
+                        changed = true;
+                    }
+                    Some(_) => {}
+                    None => {
+                        meals.push(template_meal);
+                        changed = true;
+                    }
+                }
+            }
+
+            if changed {
+                result.push(ResponseDay {
+                    id: day.id,
+                    date: day.date,
+                    meals,
+                    note: None,
+                });
+            }
+        }
+
+        result
+    }
+}
+
+/// Requests to save the week that contains `week` as a template.
+#[derive(Debug, Deserialize)]
+pub struct SaveTemplateRequest {
+    pub name: String,
+    pub week: NaiveDate,
+}
+
+/// Requests to apply a template to the week that contains `week`.
+#[derive(Debug, Deserialize)]
+pub struct ApplyTemplateRequest {
+    pub week: NaiveDate,
+    #[serde(default)]
+    pub mode: ApplyMode,
+}